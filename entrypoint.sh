@@ -4,4 +4,4 @@ do
     /app/diesel database setup && break;
 done
 /app/diesel migration run
-/app/testaustime-rs
+exec /app/testaustime-rs
@@ -0,0 +1,4 @@
+ALTER TABLE user_identities
+DROP COLUMN timezone;
+ALTER TABLE coding_activities
+ALTER COLUMN start_time TYPE TIMESTAMP;
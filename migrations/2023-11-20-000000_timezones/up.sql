@@ -0,0 +1,6 @@
+-- NOTE: The existing start times were stored in the local time of the server,
+-- so this has to be run with the same TimeZone setting as the server
+ALTER TABLE coding_activities
+ALTER COLUMN start_time TYPE TIMESTAMPTZ;
+ALTER TABLE user_identities
+ADD COLUMN timezone VARCHAR(64) NOT NULL DEFAULT 'UTC';
@@ -0,0 +1 @@
+DROP TABLE coding_goals;
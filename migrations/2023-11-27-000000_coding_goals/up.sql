@@ -0,0 +1,8 @@
+CREATE TABLE coding_goals(
+    user_id INTEGER PRIMARY KEY,
+    daily_goal INTEGER,
+    weekly_goal INTEGER,
+    FOREIGN KEY(user_id)
+        REFERENCES user_identities(id)
+            ON DELETE CASCADE
+);
@@ -0,0 +1,4 @@
+ALTER TABLE leaderboards
+DROP COLUMN time_window,
+DROP COLUMN window_days,
+DROP COLUMN metric;
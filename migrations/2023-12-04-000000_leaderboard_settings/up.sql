@@ -0,0 +1,4 @@
+ALTER TABLE leaderboards
+ADD COLUMN time_window VARCHAR(16) NOT NULL DEFAULT 'week',
+ADD COLUMN window_days INTEGER,
+ADD COLUMN metric VARCHAR(16) NOT NULL DEFAULT 'time';
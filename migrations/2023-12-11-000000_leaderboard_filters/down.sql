@@ -0,0 +1,4 @@
+ALTER TABLE leaderboards
+DROP COLUMN languages,
+DROP COLUMN project_pattern,
+DROP COLUMN editor;
@@ -0,0 +1,4 @@
+ALTER TABLE leaderboards
+ADD COLUMN languages TEXT[],
+ADD COLUMN project_pattern VARCHAR(64),
+ADD COLUMN editor VARCHAR(32);
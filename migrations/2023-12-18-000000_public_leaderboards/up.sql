@@ -0,0 +1 @@
+ALTER TABLE leaderboards ADD COLUMN public BOOLEAN NOT NULL DEFAULT false;
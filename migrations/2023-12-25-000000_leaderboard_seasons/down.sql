@@ -0,0 +1,4 @@
+ALTER TABLE leaderboards ALTER COLUMN creation_time TYPE TIMESTAMP;
+ALTER TABLE leaderboards DROP COLUMN seasons_since;
+DROP TABLE leaderboard_season_rankings;
+DROP TABLE leaderboard_seasons;
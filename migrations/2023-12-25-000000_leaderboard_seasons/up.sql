@@ -0,0 +1,27 @@
+CREATE TABLE leaderboard_seasons (
+    id SERIAL PRIMARY KEY,
+    leaderboard_id INTEGER NOT NULL REFERENCES leaderboards(id) ON DELETE CASCADE,
+    period VARCHAR(16) NOT NULL,
+    start_time TIMESTAMPTZ NOT NULL,
+    end_time TIMESTAMPTZ NOT NULL,
+    metric VARCHAR(16) NOT NULL,
+    UNIQUE (leaderboard_id, period)
+);
+
+CREATE TABLE leaderboard_season_rankings (
+    season_id INTEGER NOT NULL REFERENCES leaderboard_seasons(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    user_id INTEGER REFERENCES user_identities(id) ON DELETE SET NULL,
+    username VARCHAR(32) NOT NULL,
+    time_coded INTEGER NOT NULL,
+    score BIGINT NOT NULL,
+    PRIMARY KEY (season_id, position)
+);
+
+-- Seasons are only recorded from the time they started to be tracked, not since the
+-- leaderboard was created
+ALTER TABLE leaderboards ADD COLUMN seasons_since TIMESTAMPTZ NOT NULL DEFAULT NOW();
+
+-- The creation times were stored in the local time of the server, which is assumed to
+-- be the timezone of the database
+ALTER TABLE leaderboards ALTER COLUMN creation_time TYPE TIMESTAMPTZ;
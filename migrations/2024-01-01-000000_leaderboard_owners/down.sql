@@ -0,0 +1,4 @@
+ALTER TABLE leaderboards
+DROP COLUMN description,
+DROP COLUMN avatar_url,
+DROP COLUMN owner_id;
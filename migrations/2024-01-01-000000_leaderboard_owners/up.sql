@@ -0,0 +1,11 @@
+ALTER TABLE leaderboards
+ADD COLUMN description VARCHAR(512),
+ADD COLUMN avatar_url VARCHAR(256),
+ADD COLUMN owner_id INTEGER REFERENCES user_identities(id) ON DELETE SET NULL;
+
+UPDATE leaderboards l SET owner_id = (
+    SELECT m.user_id FROM leaderboard_members m
+    WHERE m.leaderboard_id = l.id AND m.admin
+    ORDER BY m.id
+    LIMIT 1
+);
@@ -0,0 +1,12 @@
+CREATE TABLE leaderboard_invites (
+    id SERIAL PRIMARY KEY,
+    leaderboard_id INTEGER NOT NULL REFERENCES leaderboards(id) ON DELETE CASCADE,
+    name VARCHAR(32) NOT NULL,
+    invite_code VARCHAR(32) NOT NULL UNIQUE,
+    created_by INTEGER REFERENCES user_identities(id) ON DELETE SET NULL,
+    creation_time TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ,
+    max_uses INTEGER,
+    uses INTEGER NOT NULL DEFAULT 0,
+    UNIQUE (leaderboard_id, name)
+);
@@ -0,0 +1,3 @@
+DROP TABLE leaderboard_join_requests;
+
+ALTER TABLE leaderboards DROP COLUMN approval_required;
@@ -0,0 +1,10 @@
+ALTER TABLE leaderboards ADD COLUMN approval_required BOOLEAN NOT NULL DEFAULT false;
+
+CREATE TABLE leaderboard_join_requests (
+    id SERIAL PRIMARY KEY,
+    leaderboard_id INTEGER NOT NULL REFERENCES leaderboards(id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES user_identities(id) ON DELETE CASCADE,
+    invite_id INTEGER REFERENCES leaderboard_invites(id) ON DELETE SET NULL,
+    creation_time TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (leaderboard_id, user_id)
+);
@@ -0,0 +1,8 @@
+CREATE TABLE leaderboard_bans (
+    id SERIAL PRIMARY KEY,
+    leaderboard_id INTEGER NOT NULL REFERENCES leaderboards(id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES user_identities(id) ON DELETE CASCADE,
+    banned_by INTEGER REFERENCES user_identities(id) ON DELETE SET NULL,
+    creation_time TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (leaderboard_id, user_id)
+);
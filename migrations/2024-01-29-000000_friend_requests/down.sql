@@ -0,0 +1,6 @@
+DROP TABLE user_blocks;
+
+DELETE FROM friend_relations WHERE NOT accepted;
+ALTER TABLE friend_relations DROP COLUMN creation_time;
+ALTER TABLE friend_relations DROP COLUMN accepted;
+ALTER TABLE friend_relations DROP COLUMN requester_id;
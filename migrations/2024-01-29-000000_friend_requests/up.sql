@@ -0,0 +1,12 @@
+-- NOTE: Existing friends were added with friend codes and stay friends
+ALTER TABLE friend_relations ADD COLUMN requester_id INTEGER REFERENCES user_identities(id) ON DELETE CASCADE;
+ALTER TABLE friend_relations ADD COLUMN accepted BOOLEAN NOT NULL DEFAULT true;
+ALTER TABLE friend_relations ADD COLUMN creation_time TIMESTAMPTZ NOT NULL DEFAULT NOW();
+
+CREATE TABLE user_blocks (
+    id SERIAL PRIMARY KEY,
+    blocker_id INTEGER NOT NULL REFERENCES user_identities(id) ON DELETE CASCADE,
+    blocked_id INTEGER NOT NULL REFERENCES user_identities(id) ON DELETE CASCADE,
+    creation_time TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (blocker_id, blocked_id)
+);
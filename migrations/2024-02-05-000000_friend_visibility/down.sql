@@ -0,0 +1 @@
+DROP TABLE friend_visibility;
@@ -0,0 +1,8 @@
+CREATE TABLE friend_visibility (
+    user_id INTEGER NOT NULL REFERENCES user_identities(id) ON DELETE CASCADE,
+    friend_id INTEGER NOT NULL REFERENCES user_identities(id) ON DELETE CASCADE,
+    hide_project_names BOOLEAN NOT NULL DEFAULT false,
+    hide_hostnames BOOLEAN NOT NULL DEFAULT false,
+    hide_editors BOOLEAN NOT NULL DEFAULT false,
+    PRIMARY KEY (user_id, friend_id)
+);
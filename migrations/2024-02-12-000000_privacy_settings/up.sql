@@ -0,0 +1,7 @@
+CREATE TABLE privacy_settings (
+    user_id INTEGER PRIMARY KEY REFERENCES user_identities(id) ON DELETE CASCADE,
+    hide_project_names BOOLEAN NOT NULL DEFAULT false,
+    hide_hostnames BOOLEAN NOT NULL DEFAULT false,
+    hide_editors BOOLEAN NOT NULL DEFAULT false,
+    private_projects TEXT[] NOT NULL DEFAULT '{}'
+);
@@ -0,0 +1,9 @@
+CREATE TABLE project_aliases (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES user_identities(id) ON DELETE CASCADE,
+    pattern VARCHAR(64) NOT NULL,
+    match_kind VARCHAR(8) NOT NULL DEFAULT 'exact',
+    target VARCHAR(64) NOT NULL,
+    creation_time TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (user_id, pattern)
+);
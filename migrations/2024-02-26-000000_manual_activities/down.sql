@@ -0,0 +1,2 @@
+ALTER TABLE leaderboards DROP COLUMN exclude_manual;
+ALTER TABLE coding_activities DROP COLUMN source;
@@ -0,0 +1,2 @@
+ALTER TABLE coding_activities ADD COLUMN source VARCHAR(8) NOT NULL DEFAULT 'editor';
+ALTER TABLE leaderboards ADD COLUMN exclude_manual BOOLEAN NOT NULL DEFAULT false;
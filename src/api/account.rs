@@ -1,11 +1,26 @@
 use actix_web::{web, HttpResponse, Responder};
+use chrono_tz::Tz;
 use serde_derive::Deserialize;
 
-use crate::{api::auth::SecuredUserIdentity, database::DatabaseWrapper, error::TimeError};
+use crate::{
+    api::auth::SecuredUserIdentity,
+    database::DatabaseWrapper,
+    error::TimeError,
+    models::{PrivacySettings, UserId},
+};
 
 #[derive(Deserialize)]
 pub struct Settings {
     public_profile: Option<bool>,
+    timezone: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct PrivacySettingsRequest {
+    hide_project_names: Option<bool>,
+    hide_hostnames: Option<bool>,
+    hide_editors: Option<bool>,
+    private_projects: Option<Vec<String>>,
 }
 
 #[post("/account/settings")]
@@ -14,10 +29,74 @@ pub async fn change_settings(
     userid: SecuredUserIdentity,
     db: DatabaseWrapper,
 ) -> Result<impl Responder, TimeError> {
+    let timezone = settings
+        .timezone
+        .as_deref()
+        .map(|timezone| timezone.parse::<Tz>().map_err(|_| TimeError::BadTimezone))
+        .transpose()?;
+
     if let Some(public_profile) = settings.public_profile {
         db.change_visibility(userid.identity.id, public_profile)
             .await?;
     };
 
+    if let Some(timezone) = timezone {
+        db.change_timezone(userid.identity.id, timezone).await?;
+    };
+
     Ok(HttpResponse::Ok())
 }
+
+#[get("/account/privacy")]
+pub async fn get_privacy_settings(
+    user: UserId,
+    db: DatabaseWrapper,
+) -> Result<impl Responder, TimeError> {
+    Ok(web::Json(db.get_privacy_settings(user.id).await?))
+}
+
+/// Missing settings keep their current value, the list of private projects is replaced
+#[post("/account/privacy")]
+pub async fn change_privacy_settings(
+    settings: web::Json<PrivacySettingsRequest>,
+    userid: SecuredUserIdentity,
+    db: DatabaseWrapper,
+) -> Result<impl Responder, TimeError> {
+    let current = db.get_privacy_settings(userid.identity.id).await?;
+
+    let private_projects = match &settings.private_projects {
+        None => current.private_projects,
+        Some(projects) => {
+            if projects.len() > 100
+                || projects
+                    .iter()
+                    .any(|project| project.trim().is_empty() || project.len() > 64)
+            {
+                return Err(TimeError::InvalidLength(
+                    "There can be at most 100 private projects of 1 to 64 characters".to_string(),
+                ));
+            }
+            let mut projects: Vec<String> = projects
+                .iter()
+                .map(|project| project.trim().to_string())
+                .collect();
+            projects.sort();
+            projects.dedup();
+            projects
+        }
+    };
+
+    let privacy = PrivacySettings {
+        user_id: userid.identity.id,
+        hide_project_names: settings
+            .hide_project_names
+            .unwrap_or(current.hide_project_names),
+        hide_hostnames: settings.hide_hostnames.unwrap_or(current.hide_hostnames),
+        hide_editors: settings.hide_editors.unwrap_or(current.hide_editors),
+        private_projects,
+    };
+
+    db.set_privacy_settings(privacy.clone()).await?;
+
+    Ok(web::Json(privacy))
+}
@@ -1,18 +1,79 @@
+use std::sync::Arc;
+
 use actix_web::{
     error::*,
     web::{self, Data, Json},
     HttpResponse, Responder,
 };
-use chrono::{Duration, Local};
-use dashmap::DashMap;
+use chrono::{DateTime, Duration, Utc};
+use dashmap::{mapref::entry::Entry, DashMap};
 use serde_derive::Deserialize;
 
 use crate::{
-    api::auth::SecuredUserIdentity, database::DatabaseWrapper, error::TimeError, models::UserId,
+    api::auth::SecuredUserIdentity,
+    database::DatabaseWrapper,
+    error::TimeError,
+    models::{
+        ActivityChanges, AliasMatch, ManualActivity, NewProjectAlias, ProjectAliasRules, UserId,
+    },
     requests::*,
 };
 
-pub type HeartBeatMemoryStore = DashMap<i32, (HeartBeat, chrono::NaiveDateTime, chrono::Duration)>;
+pub type HeartBeatMemoryStore = DashMap<i32, Session>;
+
+/// The compiled project alias rules of users, removed when the aliases of the user change
+pub type ProjectAliasCache = DashMap<i32, Arc<ProjectAliasRules>>;
+
+/// A coding session: the heartbeat, its start time and its duration so far.
+pub type Session = (HeartBeat, DateTime<Utc>, Duration);
+
+pub const MAX_BATCH_SIZE: usize = 1000;
+
+/// How long a coding session can go without heartbeats before it's finished
+pub struct SessionIdleTimeout(pub Duration);
+
+/// Writes every open session to the database and empties the store.
+/// Used on shutdown so that restarting the server doesn't lose tracked time.
+pub async fn flush_all_heartbeats(db: &DatabaseWrapper, heartbeats: &HeartBeatMemoryStore) {
+    flush_heartbeats_where(db, heartbeats, |_| true).await;
+}
+
+/// Writes sessions that have not received a heartbeat in `max_idle` to the database
+/// and removes them from the store.
+pub async fn flush_idle_heartbeats(
+    db: &DatabaseWrapper,
+    heartbeats: &HeartBeatMemoryStore,
+    max_idle: Duration,
+) {
+    let curtime = Utc::now();
+    flush_heartbeats_where(db, heartbeats, |(_, start, duration)| {
+        curtime.signed_duration_since(*start + *duration) > max_idle
+    })
+    .await;
+}
+
+async fn flush_heartbeats_where(
+    db: &DatabaseWrapper,
+    heartbeats: &HeartBeatMemoryStore,
+    predicate: impl Fn(&Session) -> bool,
+) {
+    let users = heartbeats
+        .iter()
+        .filter(|s| predicate(s.value()))
+        .map(|s| *s.key())
+        .collect::<Vec<_>>();
+
+    for user in users {
+        // NOTE: The session might have been extended after it was selected
+        if let Some((user, (heartbeat, start, duration))) =
+            heartbeats.remove_if(&user, |_, session| predicate(session))
+        {
+            if let Err(e) = db.add_activity(user, heartbeat, start, duration).await {
+                error!("Failed to flush the session of user {}: {}", user, e);
+            }
+        }
+    }
+}
 
 #[derive(Deserialize)]
 pub struct RenameRequest {
@@ -20,13 +81,50 @@ pub struct RenameRequest {
     to: String,
 }
 
-#[post("/update")]
-pub async fn update(
-    user: UserId,
-    heartbeat: Json<HeartBeat>,
-    db: DatabaseWrapper,
-    heartbeats: Data<HeartBeatMemoryStore>,
-) -> Result<impl Responder, TimeError> {
+#[derive(Deserialize)]
+pub struct ProjectAliasRequest {
+    pattern: String,
+    #[serde(default)]
+    match_kind: AliasMatch,
+    target: String,
+    /// Whether to also rename the projects of existing activities
+    #[serde(default)]
+    backfill: bool,
+}
+
+const MAX_PROJECT_ALIASES: i64 = 100;
+
+#[derive(Deserialize)]
+pub struct ManualActivityRequest {
+    #[serde(with = "chrono::serde::ts_seconds")]
+    start_time: DateTime<Utc>,
+    duration: i32,
+    project_name: Option<String>,
+    language: Option<String>,
+}
+
+/// The longest manual activity, in seconds
+const MAX_MANUAL_DURATION: i32 = 24 * 60 * 60;
+
+#[derive(Deserialize)]
+pub struct BulkDeleteRequest {
+    filter: DataRequest,
+    /// Whether to only count the matching activities
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Deserialize)]
+pub struct BulkUpdateRequest {
+    filter: DataRequest,
+    set: ActivityChanges,
+    /// Whether to only count the matching activities
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// Checks that the fields of a heartbeat fit in the database.
+pub fn validate_heartbeat(heartbeat: &HeartBeat) -> Result<(), TimeError> {
     if let Some(project) = &heartbeat.project_name {
         if project.len() > 64 {
             return Err(TimeError::InvalidLength(
@@ -55,39 +153,33 @@ pub async fn update(
             ));
         }
     }
-    match heartbeats.get(&user.id) {
-        Some(activity) => {
-            let (current_heartbeat, start, mut duration) = activity.to_owned();
-            drop(activity);
-            let curtime = Local::now().naive_local();
+    Ok(())
+}
+
+/// Applies a heartbeat received at `curtime` to the current coding session.
+/// Returns the new current session and the finished session that should be stored, if any.
+fn merge_heartbeat(
+    current: Option<Session>,
+    heartbeat: HeartBeat,
+    curtime: DateTime<Utc>,
+    idle_timeout: Duration,
+) -> (Session, Option<Session>) {
+    match current {
+        Some((current_heartbeat, start, mut duration)) => {
             if heartbeat.eq(&current_heartbeat) {
-                if curtime.signed_duration_since(start + duration) > Duration::seconds(900) {
+                if curtime.signed_duration_since(start + duration) > idle_timeout {
                     // If the user sends a heartbeat but maximum activity duration has been exceeded,
                     // end session and start new
-                    db.add_activity(user.id, current_heartbeat, start, duration)
-                        .await
-                        .map_err(ErrorInternalServerError)?;
-
-                    heartbeats.insert(
-                        user.id,
-                        (
-                            heartbeat.into_inner(),
-                            Local::now().naive_local(),
-                            Duration::seconds(0),
-                        ),
-                    );
-                    Ok(HttpResponse::Ok().body(0i32.to_string()))
+                    (
+                        (heartbeat, curtime, Duration::seconds(0)),
+                        Some((current_heartbeat, start, duration)),
+                    )
                 } else {
                     // Extend current coding session if heartbeat matches and it has been under the maximum duration of a break
-                    heartbeats.insert(
-                        user.id,
-                        (
-                            heartbeat.into_inner(),
-                            start,
-                            curtime.signed_duration_since(start),
-                        ),
-                    );
-                    Ok(HttpResponse::Ok().body(curtime.signed_duration_since(start).to_string()))
+                    (
+                        (heartbeat, start, curtime.signed_duration_since(start)),
+                        None,
+                    )
                 }
             } else {
                 // Flush current session and start new session if heartbeat changes
@@ -95,34 +187,214 @@ pub async fn update(
                     duration = curtime.signed_duration_since(start);
                 }
 
-                db.add_activity(user.id, current_heartbeat, start, duration)
-                    .await
-                    .map_err(ErrorInternalServerError)?;
+                (
+                    (heartbeat, curtime, Duration::seconds(0)),
+                    Some((current_heartbeat, start, duration)),
+                )
+            }
+        }
+        // If the user has not sent a heartbeat during this session
+        None => ((heartbeat, curtime, Duration::seconds(0)), None),
+    }
+}
 
-                heartbeats.insert(
-                    user.id,
-                    (
-                        heartbeat.into_inner(),
-                        Local::now().naive_local(),
-                        Duration::seconds(0),
-                    ),
-                );
-                Ok(HttpResponse::Ok().body(0i32.to_string()))
+/// Merges heartbeats recorded at the given times into coding sessions.
+/// Sessions of a single heartbeat have no length and are left out.
+pub fn merge_heartbeats(
+    mut items: Vec<(HeartBeat, DateTime<Utc>)>,
+    idle_timeout: Duration,
+) -> Vec<Session> {
+    items.sort_by_key(|(_, time)| *time);
+
+    let mut sessions = Vec::new();
+    let mut current = None;
+    for (heartbeat, time) in items {
+        let (session, finished) = merge_heartbeat(current, heartbeat, time, idle_timeout);
+        sessions.extend(finished);
+        current = Some(session);
+    }
+    sessions.extend(current);
+    sessions.retain(|(_, _, duration)| *duration > Duration::zero());
+
+    sessions
+}
+
+/// Applies heartbeats recorded at the given times to the current session.
+/// Heartbeats older than the current session are merged into separate sessions.
+/// Returns the finished sessions and the new current session.
+fn merge_into_session(
+    mut current: Option<Session>,
+    mut items: Vec<(HeartBeat, DateTime<Utc>)>,
+    idle_timeout: Duration,
+) -> (Vec<Session>, Option<Session>) {
+    items.sort_by_key(|(_, time)| *time);
+
+    let last_seen = current
+        .as_ref()
+        .map(|(_, start, duration)| *start + *duration);
+
+    let (old, new): (Vec<_>, Vec<_>) = items
+        .into_iter()
+        .partition(|(_, time)| last_seen.is_some_and(|last_seen| *time < last_seen));
+
+    let mut sessions = merge_heartbeats(old, idle_timeout);
+    for (heartbeat, time) in new {
+        let (session, finished) = merge_heartbeat(current, heartbeat, time, idle_timeout);
+        sessions.extend(finished);
+        current = Some(session);
+    }
+    sessions.retain(|(_, _, duration)| *duration > Duration::zero());
+
+    (sessions, current)
+}
+
+/// Applies heartbeats recorded at the given times to the current session of the user.
+/// Heartbeats older than the current session are stored as separate sessions.
+pub async fn apply_heartbeats(
+    user_id: i32,
+    items: Vec<(HeartBeat, DateTime<Utc>)>,
+    idle_timeout: Duration,
+    db: &DatabaseWrapper,
+    heartbeats: &HeartBeatMemoryStore,
+) -> Result<(), TimeError> {
+    // NOTE: The session is updated while holding the entry so concurrent requests of the
+    // same user can't overwrite each other's changes
+    let sessions = match heartbeats.entry(user_id) {
+        Entry::Occupied(mut entry) => {
+            let (sessions, current) =
+                merge_into_session(Some(entry.get().to_owned()), items, idle_timeout);
+            if let Some(current) = current {
+                entry.insert(current);
             }
+            sessions
         }
-        None => {
-            // If the user has not sent a heartbeat during this session
-            heartbeats.insert(
-                user.id,
-                (
-                    heartbeat.into_inner(),
-                    Local::now().naive_local(),
-                    Duration::seconds(0),
-                ),
+        Entry::Vacant(entry) => {
+            let (sessions, current) = merge_into_session(None, items, idle_timeout);
+            if let Some(current) = current {
+                entry.insert(current);
+            }
+            sessions
+        }
+    };
+
+    db.add_activities(user_id, sessions).await?;
+
+    Ok(())
+}
+
+/// Gets the project alias rules of the user, compiling them only if they aren't cached
+pub async fn get_alias_rules(
+    user: i32,
+    db: &DatabaseWrapper,
+    alias_cache: &ProjectAliasCache,
+) -> Result<Arc<ProjectAliasRules>, TimeError> {
+    if let Some(rules) = alias_cache.get(&user) {
+        return Ok(Arc::clone(&rules));
+    }
+
+    let rules = Arc::new(db.get_project_alias_rules(user).await?);
+    alias_cache.insert(user, Arc::clone(&rules));
+
+    Ok(rules)
+}
+
+#[post("/update")]
+pub async fn update(
+    user: UserId,
+    heartbeat: Json<HeartBeat>,
+    db: DatabaseWrapper,
+    heartbeats: Data<HeartBeatMemoryStore>,
+    idle_timeout: Data<SessionIdleTimeout>,
+    alias_cache: Data<ProjectAliasCache>,
+) -> Result<impl Responder, TimeError> {
+    let heartbeat = get_alias_rules(user.id, &db, &alias_cache)
+        .await?
+        .apply_heartbeat(heartbeat.into_inner());
+    validate_heartbeat(&heartbeat)?;
+
+    let curtime = Utc::now();
+
+    // NOTE: The session is updated while holding the entry so concurrent heartbeats of the
+    // same user can't lose a session or finish it twice
+    let (finished, extended) = match heartbeats.entry(user.id) {
+        Entry::Occupied(mut entry) => {
+            let (session, finished) = merge_heartbeat(
+                Some(entry.get().to_owned()),
+                heartbeat,
+                curtime,
+                idle_timeout.0,
             );
-            Ok(HttpResponse::Ok().body(0.to_string()))
+            let extended = finished.is_none().then_some(session.2);
+            entry.insert(session);
+            (finished, extended)
+        }
+        Entry::Vacant(entry) => {
+            let (session, _) = merge_heartbeat(None, heartbeat, curtime, idle_timeout.0);
+            entry.insert(session);
+            (None, None)
+        }
+    };
+
+    if let Some((finished_heartbeat, start, duration)) = finished {
+        db.add_activity(user.id, finished_heartbeat, start, duration)
+            .await
+            .map_err(ErrorInternalServerError)?;
+    }
+
+    match extended {
+        Some(duration) => Ok(HttpResponse::Ok().body(duration.to_string())),
+        None => Ok(HttpResponse::Ok().body(0i32.to_string())),
+    }
+}
+
+#[post("/batch")]
+pub async fn batch(
+    user: UserId,
+    body: Json<Vec<TimestampedHeartBeat>>,
+    db: DatabaseWrapper,
+    idle_timeout: Data<SessionIdleTimeout>,
+    alias_cache: Data<ProjectAliasCache>,
+) -> Result<impl Responder, TimeError> {
+    if body.len() > MAX_BATCH_SIZE {
+        return Err(TimeError::InvalidLength(format!(
+            "Batch is over {MAX_BATCH_SIZE} heartbeats"
+        )));
+    }
+
+    let aliases = get_alias_rules(user.id, &db, &alias_cache).await?;
+    let now = Utc::now();
+    let mut errors = Vec::new();
+    let mut valid = Vec::new();
+
+    for (index, mut item) in body.into_inner().into_iter().enumerate() {
+        item.heartbeat = aliases.apply_heartbeat(item.heartbeat);
+        let result = if item.timestamp > now {
+            Err(TimeError::FutureHeartBeat)
+        } else {
+            validate_heartbeat(&item.heartbeat)
+        };
+
+        match result {
+            Ok(()) => valid.push(item),
+            Err(e) => errors.push(json!({ "index": index, "error": e.to_string() })),
         }
     }
+
+    let sessions = merge_heartbeats(
+        valid
+            .into_iter()
+            .map(|item| (item.heartbeat, item.timestamp))
+            .collect(),
+        idle_timeout.0,
+    );
+
+    let (session_count, skipped_count) = db.add_activities(user.id, sessions).await?;
+
+    Ok(web::Json(json!({
+        "sessions": session_count,
+        "skipped_sessions": skipped_count,
+        "errors": errors,
+    })))
 }
 
 #[post("/flush")]
@@ -131,10 +403,7 @@ pub async fn flush(
     db: DatabaseWrapper,
     heartbeats: Data<HeartBeatMemoryStore>,
 ) -> Result<impl Responder, TimeError> {
-    if let Some(heartbeat) = heartbeats.get(&user.id) {
-        let (inner_heartbeat, start, duration) = heartbeat.to_owned();
-        drop(heartbeat);
-        heartbeats.remove(&user.id);
+    if let Some((_, (inner_heartbeat, start, duration))) = heartbeats.remove(&user.id) {
         db.add_activity(user.id, inner_heartbeat, start, duration)
             .await?;
     }
@@ -160,6 +429,179 @@ pub async fn delete(
     }
 }
 
+#[post("/bulk/delete")]
+pub async fn bulk_delete(
+    user: SecuredUserIdentity,
+    db: DatabaseWrapper,
+    body: Json<BulkDeleteRequest>,
+) -> Result<impl Responder, TimeError> {
+    let body = body.into_inner();
+    if !body.filter.has_filter() {
+        return Err(TimeError::MissingFilter);
+    }
+
+    let affected = db
+        .delete_activities(user.identity.id, body.filter, body.dry_run)
+        .await?;
+
+    Ok(web::Json(json!({
+        "dry_run": body.dry_run,
+        "affected_activities": affected,
+    })))
+}
+
+/// Whether the filters of a bulk edit match the current session of the user.
+/// Paginated edits only affect stored activities.
+fn session_matches(filter: &DataRequest, (heartbeat, start, duration): &Session) -> bool {
+    let matches =
+        |wanted: &Option<String>, value: &Option<String>| wanted.is_none() || wanted == value;
+
+    filter.limit.is_none()
+        && filter.cursor.is_none()
+        && filter.from.is_none_or(|from| *start >= from)
+        && filter.to.is_none_or(|to| *start <= to)
+        && filter
+            .min_duration
+            .is_none_or(|min| duration.num_seconds() >= min.into())
+        && matches(&filter.editor_name, &heartbeat.editor_name)
+        && matches(&filter.language, &heartbeat.language)
+        && matches(&filter.hostname, &heartbeat.hostname)
+        && matches(&filter.project_name, &heartbeat.project_name)
+}
+
+#[post("/bulk/update")]
+pub async fn bulk_update(
+    user: SecuredUserIdentity,
+    db: DatabaseWrapper,
+    body: Json<BulkUpdateRequest>,
+    heartbeats: Data<HeartBeatMemoryStore>,
+    alias_cache: Data<ProjectAliasCache>,
+) -> Result<impl Responder, TimeError> {
+    let mut body = body.into_inner();
+    if !body.filter.has_filter() {
+        return Err(TimeError::MissingFilter);
+    }
+    if body.set.is_empty() {
+        return Err(TimeError::NoChanges);
+    }
+    let aliases = get_alias_rules(user.identity.id, &db, &alias_cache).await?;
+    body.set.project_name = body
+        .set
+        .project_name
+        .map(|project| aliases.apply(normalize_project_name(project)));
+    validate_heartbeat(&HeartBeat {
+        project_name: body.set.project_name.clone(),
+        language: body.set.language.clone(),
+        editor_name: body.set.editor_name.clone(),
+        hostname: body.set.hostname.clone(),
+    })?;
+
+    let affected = db
+        .update_activities(
+            user.identity.id,
+            body.filter.clone(),
+            body.set.clone(),
+            body.dry_run,
+        )
+        .await?;
+
+    if !body.dry_run {
+        // NOTE: The current session is changed too, so that it isn't stored with the old
+        // values when it's flushed
+        if let Some(mut session) = heartbeats.get_mut(&user.identity.id) {
+            if session_matches(&body.filter, &session) {
+                let changes = body.set;
+                let heartbeat = &mut session.0;
+                heartbeat.project_name = changes.project_name.or(heartbeat.project_name.take());
+                heartbeat.language = changes.language.or(heartbeat.language.take());
+                heartbeat.editor_name = changes.editor_name.or(heartbeat.editor_name.take());
+                heartbeat.hostname = changes.hostname.or(heartbeat.hostname.take());
+            }
+        }
+    }
+
+    Ok(web::Json(json!({
+        "dry_run": body.dry_run,
+        "affected_activities": affected,
+    })))
+}
+
+fn validate_manual_activity(
+    request: ManualActivityRequest,
+    aliases: &ProjectAliasRules,
+) -> Result<ManualActivity, TimeError> {
+    if !(1..=MAX_MANUAL_DURATION).contains(&request.duration) {
+        return Err(TimeError::InvalidLength(
+            "Duration has to be between 1 second and 24 hours".to_string(),
+        ));
+    }
+    if request.start_time + Duration::seconds(request.duration.into()) > Utc::now() {
+        return Err(TimeError::FutureActivity);
+    }
+
+    let activity = ManualActivity {
+        start_time: request.start_time,
+        duration: request.duration,
+        project_name: request
+            .project_name
+            .map(|project| aliases.apply(normalize_project_name(project))),
+        language: request.language,
+    };
+
+    validate_heartbeat(&HeartBeat {
+        project_name: activity.project_name.clone(),
+        language: activity.language.clone(),
+        editor_name: None,
+        hostname: None,
+    })?;
+
+    Ok(activity)
+}
+
+#[post("/manual")]
+pub async fn add_manual_activity(
+    user: UserId,
+    db: DatabaseWrapper,
+    body: Json<ManualActivityRequest>,
+    alias_cache: Data<ProjectAliasCache>,
+) -> Result<impl Responder, TimeError> {
+    let aliases = get_alias_rules(user.id, &db, &alias_cache).await?;
+    let activity = validate_manual_activity(body.into_inner(), &aliases)?;
+
+    Ok(web::Json(db.add_manual_activity(user.id, activity).await?))
+}
+
+/// Replaces a manual activity, missing fields are cleared
+#[post("/manual/{id}")]
+pub async fn edit_manual_activity(
+    user: UserId,
+    db: DatabaseWrapper,
+    path: web::Path<(i32,)>,
+    body: Json<ManualActivityRequest>,
+    alias_cache: Data<ProjectAliasCache>,
+) -> Result<impl Responder, TimeError> {
+    let aliases = get_alias_rules(user.id, &db, &alias_cache).await?;
+    let activity = validate_manual_activity(body.into_inner(), &aliases)?;
+
+    match db.update_manual_activity(user.id, path.0, activity).await? {
+        Some(activity) => Ok(web::Json(activity)),
+        None => Err(TimeError::BadId),
+    }
+}
+
+#[delete("/manual/{id}")]
+pub async fn delete_manual_activity(
+    user: UserId,
+    db: DatabaseWrapper,
+    path: web::Path<(i32,)>,
+) -> Result<impl Responder, TimeError> {
+    if db.delete_manual_activity(user.id, path.0).await? {
+        Ok(HttpResponse::Ok().finish())
+    } else {
+        Err(TimeError::BadId)
+    }
+}
+
 #[post("/rename")]
 pub async fn rename_project(
     user: UserId,
@@ -172,3 +614,78 @@ pub async fn rename_project(
 
     Ok(web::Json(json!({ "affected_activities": renamed })))
 }
+
+#[get("/aliases")]
+pub async fn get_aliases(user: UserId, db: DatabaseWrapper) -> Result<impl Responder, TimeError> {
+    Ok(web::Json(db.get_project_aliases(user.id).await?))
+}
+
+#[post("/aliases")]
+pub async fn add_alias(
+    user: UserId,
+    db: DatabaseWrapper,
+    body: Json<ProjectAliasRequest>,
+    alias_cache: Data<ProjectAliasCache>,
+) -> Result<impl Responder, TimeError> {
+    let body = body.into_inner();
+    if body.pattern.is_empty() || body.pattern.len() > 64 {
+        return Err(TimeError::InvalidLength(
+            "Pattern has to be between 1 and 64 characters".to_string(),
+        ));
+    }
+    if body.target.trim().is_empty() || body.target.len() > 64 {
+        return Err(TimeError::InvalidLength(
+            "Target has to be between 1 and 64 characters".to_string(),
+        ));
+    }
+    body.match_kind
+        .compile(&body.pattern)
+        .map_err(|e| TimeError::BadAliasPattern(e.to_string()))?;
+
+    let alias = match db
+        .add_project_alias(
+            NewProjectAlias {
+                user_id: user.id,
+                pattern: body.pattern,
+                match_kind: body.match_kind,
+                target: body.target.trim().to_string(),
+            },
+            MAX_PROJECT_ALIASES,
+        )
+        .await
+    {
+        Err(TimeError::DieselError(diesel::result::Error::DatabaseError(
+            diesel::result::DatabaseErrorKind::UniqueViolation,
+            ..,
+        ))) => return Err(TimeError::AliasExists),
+        result => result?,
+    };
+    alias_cache.remove(&user.id);
+
+    let affected = if body.backfill {
+        let rules = db.get_project_alias_rules(user.id).await?;
+        db.backfill_project_aliases(user.id, rules).await?
+    } else {
+        0
+    };
+
+    Ok(web::Json(json!({
+        "alias": alias,
+        "affected_activities": affected,
+    })))
+}
+
+#[delete("/aliases/{id}")]
+pub async fn delete_alias(
+    user: UserId,
+    db: DatabaseWrapper,
+    path: web::Path<(i32,)>,
+    alias_cache: Data<ProjectAliasCache>,
+) -> Result<impl Responder, TimeError> {
+    if db.delete_project_alias(user.id, path.0).await? {
+        alias_cache.remove(&user.id);
+        Ok(HttpResponse::Ok().finish())
+    } else {
+        Err(TimeError::BadId)
+    }
+}
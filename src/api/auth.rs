@@ -3,9 +3,11 @@ use std::{future::Future, pin::Pin};
 use actix_web::{
     dev::{ConnectionInfo, Payload},
     error::*,
+    http::header,
     web::{Data, Json},
     FromRequest, HttpMessage, HttpRequest, HttpResponse, Responder,
 };
+use base64::{prelude::BASE64_STANDARD, Engine};
 
 use crate::{
     auth::{secured_access::SecuredAccessTokenStorage, Authentication},
@@ -48,6 +50,56 @@ impl FromRequest for UserIdentity {
     }
 }
 
+/// The user of the WakaTime compatible heartbeat endpoints. WakaTime clients send the
+/// auth token with basic authentication, which isn't accepted by the other endpoints.
+pub struct WakaTimeUserId {
+    pub id: i32,
+}
+
+/// Decodes the token from basic authentication credentials, ignoring the password.
+fn decode_basic_token(credentials: &str) -> Option<String> {
+    let decoded = BASE64_STANDARD.decode(credentials).ok()?;
+    let decoded = String::from_utf8(decoded).ok()?;
+
+    decoded
+        .split(':')
+        .next()
+        .filter(|token| !token.is_empty())
+        .map(String::from)
+}
+
+impl FromRequest for WakaTimeUserId {
+    type Error = TimeError;
+    type Future = Pin<Box<dyn Future<Output = actix_web::Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let auth = req.extensions().get::<Authentication>().cloned().unwrap();
+        let token = req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|auth| auth.to_str().ok())
+            .and_then(|auth| auth.trim().strip_prefix("Basic "))
+            .and_then(decode_basic_token);
+        let db = DatabaseWrapper::from_request(req, payload);
+
+        Box::pin(async move {
+            if let Authentication::AuthToken(user) = auth {
+                return Ok(WakaTimeUserId { id: user.id });
+            }
+
+            // NOTE: The token is never accepted as a secured access token
+            let token = token.ok_or(TimeError::Unauthorized)?;
+            let user = db
+                .await?
+                .get_user_by_token(token)
+                .await
+                .map_err(|_| TimeError::Unauthorized)?;
+
+            Ok(WakaTimeUserId { id: user.id })
+        })
+    }
+}
+
 pub struct SecuredUserIdentity {
     pub identity: UserIdentity,
 }
@@ -1,6 +1,8 @@
+use std::collections::HashMap;
+
 use actix_web::{
     error::*,
-    web::{self, Data},
+    web::{self, Data, Json, Path},
     HttpResponse, Responder,
 };
 use diesel::result::DatabaseErrorKind;
@@ -9,18 +11,55 @@ use crate::{
     api::{activity::HeartBeatMemoryStore, auth::SecuredUserIdentity},
     database::DatabaseWrapper,
     error::TimeError,
-    models::{CurrentActivity, FriendWithTimeAndStatus, UserId},
+    models::{
+        CurrentActivity, FriendRequestResponse, FriendVisibility, FriendWithTimeAndStatus,
+        PrivacySettings, Redaction, UserIdentity,
+    },
+    requests::FriendVisibilityRequest,
 };
 
+fn current_activity(
+    friend: i32,
+    redaction: &Redaction,
+    heartbeats: &HeartBeatMemoryStore,
+) -> Option<CurrentActivity> {
+    heartbeats.get(&friend).map(|heartbeat| {
+        let (inner_heartbeat, start_time, duration) = heartbeat.to_owned();
+        drop(heartbeat);
+        CurrentActivity {
+            started: start_time,
+            duration: duration.num_seconds(),
+            heartbeat: redaction.redact_heartbeat(inner_heartbeat),
+        }
+    })
+}
+
+async fn friend_with_time_and_status(
+    user: &UserIdentity,
+    friend: UserIdentity,
+    db: &DatabaseWrapper,
+    heartbeats: &HeartBeatMemoryStore,
+) -> Result<FriendWithTimeAndStatus, TimeError> {
+    let redaction = db.get_redaction(friend.id, Some(user.id)).await?;
+
+    Ok(FriendWithTimeAndStatus {
+        username: friend.username.clone(),
+        coding_time: db.get_coding_time_steps(friend.id, user.tz()).await,
+        status: current_activity(friend.id, &redaction, heartbeats),
+    })
+}
+
+/// Sends a friend request by friend code or username, or accepts the request if the other
+/// user has already sent one
 #[post("/friends/add")]
 pub async fn add_friend(
-    user: UserId,
+    user: UserIdentity,
     body: String,
     db: DatabaseWrapper,
     heartbeats: Data<HeartBeatMemoryStore>,
 ) -> Result<impl Responder, TimeError> {
     match db
-        .add_friend(user.id, body.trim().trim_start_matches("ttfc_").to_string())
+        .send_friend_request(user.id, body.trim().trim_start_matches("ttfc_").to_string())
         .await
     {
         // This is not correct
@@ -30,59 +69,185 @@ pub async fn add_friend(
                 TimeError::DieselError(diesel::result::Error::DatabaseError(
                     DatabaseErrorKind::UniqueViolation,
                     ..,
-                )) => TimeError::AlreadyFriends,
+                )) => TimeError::FriendRequestExists,
                 _ => e,
             })
         }
-        Ok(friend) => {
-            let friend_with_time = FriendWithTimeAndStatus {
-                username: friend.username.clone(),
-                coding_time: db.get_coding_time_steps(friend.id).await,
-                status: heartbeats.get(&friend.id).map(|heartbeat| {
-                    let (inner_heartbeat, start_time, duration) = heartbeat.to_owned();
-                    drop(heartbeat);
-                    CurrentActivity {
-                        started: start_time,
-                        duration: duration.num_seconds(),
-                        heartbeat: inner_heartbeat,
-                    }
-                }),
-            };
-
-            Ok(web::Json(friend_with_time))
+        Ok((friend, true)) => Ok(HttpResponse::Ok()
+            .json(friend_with_time_and_status(&user, friend, &db, &heartbeats).await?)),
+        Ok((friend, false)) => {
+            Ok(HttpResponse::Accepted()
+                .json(json!({ "username": friend.username, "pending": true })))
         }
     }
 }
 
+#[get("/friends/requests")]
+pub async fn get_friend_requests(
+    user: UserIdentity,
+    db: DatabaseWrapper,
+) -> Result<impl Responder, TimeError> {
+    Ok(web::Json(db.get_friend_requests(user.id).await?))
+}
+
+async fn respond_to_friend_request(
+    user: &UserIdentity,
+    requester: String,
+    response: FriendRequestResponse,
+    db: &DatabaseWrapper,
+) -> Result<UserIdentity, TimeError> {
+    let requester = db
+        .get_user_by_name(requester.trim().to_string())
+        .await
+        .map_err(|_| TimeError::UserNotFound)?;
+
+    if db
+        .respond_to_friend_request(user.id, requester.id, response)
+        .await?
+    {
+        Ok(requester)
+    } else {
+        Err(TimeError::FriendRequestNotFound)
+    }
+}
+
+#[post("/friends/requests/accept")]
+pub async fn accept_friend_request(
+    user: UserIdentity,
+    body: String,
+    db: DatabaseWrapper,
+    heartbeats: Data<HeartBeatMemoryStore>,
+) -> Result<impl Responder, TimeError> {
+    let friend = respond_to_friend_request(&user, body, FriendRequestResponse::Accept, &db).await?;
+
+    Ok(web::Json(
+        friend_with_time_and_status(&user, friend, &db, &heartbeats).await?,
+    ))
+}
+
+#[post("/friends/requests/decline")]
+pub async fn decline_friend_request(
+    user: UserIdentity,
+    body: String,
+    db: DatabaseWrapper,
+) -> Result<impl Responder, TimeError> {
+    respond_to_friend_request(&user, body, FriendRequestResponse::Decline, &db).await?;
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[post("/friends/requests/block")]
+pub async fn block_friend_request(
+    user: UserIdentity,
+    body: String,
+    db: DatabaseWrapper,
+) -> Result<impl Responder, TimeError> {
+    respond_to_friend_request(&user, body, FriendRequestResponse::Block, &db).await?;
+
+    Ok(HttpResponse::Ok().finish())
+}
+
 #[get("/friends/list")]
 pub async fn get_friends(
-    user: UserId,
+    user: UserIdentity,
     db: DatabaseWrapper,
     heartbeats: Data<HeartBeatMemoryStore>,
 ) -> Result<impl Responder, TimeError> {
-    let friends = db
-        .get_friends_with_time(user.id)
+    let friends_with_time = db
+        .get_friends_with_time(user.id, user.tz())
         .await
-        .inspect_err(|e| error!("{e}"))?
+        .inspect_err(|e| error!("{e}"))?;
+
+    let visibilities = db
+        .get_visibilities_for_friend(user.id)
+        .await?
         .into_iter()
-        .map(|fwt| FriendWithTimeAndStatus {
-            username: fwt.user.username,
-            coding_time: fwt.coding_time,
-            status: heartbeats.get(&fwt.user.id).map(|heartbeat| {
-                let (inner_heartbeat, start_time, duration) = heartbeat.to_owned();
-                drop(heartbeat);
-                CurrentActivity {
-                    started: start_time,
-                    duration: duration.num_seconds(),
-                    heartbeat: inner_heartbeat,
-                }
-            }),
+        .map(|visibility| (visibility.user_id, visibility))
+        .collect::<HashMap<_, _>>();
+
+    let privacy_settings = db
+        .get_privacy_settings_of_users(friends_with_time.iter().map(|fwt| fwt.user.id).collect())
+        .await?
+        .into_iter()
+        .map(|privacy| (privacy.user_id, privacy))
+        .collect::<HashMap<_, _>>();
+
+    let friends = friends_with_time
+        .into_iter()
+        .map(|fwt| {
+            let redaction = Redaction::new(
+                privacy_settings
+                    .get(&fwt.user.id)
+                    .unwrap_or(&PrivacySettings::default()),
+                visibilities.get(&fwt.user.id),
+            );
+
+            FriendWithTimeAndStatus {
+                status: current_activity(fwt.user.id, &redaction, &heartbeats),
+                username: fwt.user.username,
+                coding_time: fwt.coding_time,
+            }
         })
         .collect::<Vec<_>>();
 
     Ok(web::Json(friends))
 }
 
+async fn get_friend(
+    user: &UserIdentity,
+    username: String,
+    db: &DatabaseWrapper,
+) -> Result<UserIdentity, TimeError> {
+    let friend = db
+        .get_user_by_name(username)
+        .await
+        .map_err(|_| TimeError::UserNotFound)?;
+
+    if db.are_friends(user.id, friend.id).await? {
+        Ok(friend)
+    } else {
+        Err(TimeError::NotFriends)
+    }
+}
+
+/// Returns what the authorized user shares with the friend
+#[get("/friends/{username}/visibility")]
+pub async fn get_friend_visibility(
+    user: UserIdentity,
+    path: Path<(String,)>,
+    db: DatabaseWrapper,
+) -> Result<impl Responder, TimeError> {
+    let friend = get_friend(&user, path.0.clone(), &db).await?;
+
+    Ok(web::Json(
+        db.get_friend_visibility(user.id, friend.id).await?,
+    ))
+}
+
+#[post("/friends/{username}/visibility")]
+pub async fn set_friend_visibility(
+    user: UserIdentity,
+    path: Path<(String,)>,
+    body: Json<FriendVisibilityRequest>,
+    db: DatabaseWrapper,
+) -> Result<impl Responder, TimeError> {
+    let friend = get_friend(&user, path.0.clone(), &db).await?;
+    let current = db.get_friend_visibility(user.id, friend.id).await?;
+
+    let visibility = FriendVisibility {
+        hide_project_names: body
+            .hide_project_names
+            .unwrap_or(current.hide_project_names),
+        hide_hostnames: body.hide_hostnames.unwrap_or(current.hide_hostnames),
+        hide_editors: body.hide_editors.unwrap_or(current.hide_editors),
+        ..current
+    };
+
+    db.set_friend_visibility(visibility.clone()).await?;
+
+    Ok(web::Json(visibility))
+}
+
 #[post("/friends/regenerate")]
 pub async fn regenerate_friend_code(
     user: SecuredUserIdentity,
@@ -1,15 +1,25 @@
 use actix_web::{
     error::*,
-    web::{self, Json, Path},
+    web::{self, Json, Path, Query},
     HttpResponse, Responder,
 };
+use chrono::{serde::ts_seconds_option, DateTime, Utc};
 use diesel::result::DatabaseErrorKind;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    api::auth::SecuredUserIdentity, database::DatabaseWrapper, error::TimeError, models::UserId,
+    api::auth::{SecuredUserIdentity, UserIdentityOptional},
+    database::DatabaseWrapper,
+    error::TimeError,
+    models::{
+        Leaderboard, LeaderboardConfig, LeaderboardJoin, LeaderboardMetric, LeaderboardWindow,
+        NewInviteLink, UserId, UserIdentity,
+    },
 };
 
+/// How many missed seasons of a leaderboard are recorded at most on one run
+const MAX_SEASONS_PER_RUN: usize = 10;
+
 #[derive(Deserialize, Serialize)]
 pub struct LeaderboardName {
     pub name: String,
@@ -20,27 +30,162 @@ pub struct LeaderboardInvite {
     pub invite: String,
 }
 
+#[derive(Deserialize)]
+pub struct LeaderboardSearch {
+    pub keyword: String,
+}
+
+#[derive(Deserialize)]
+pub struct CreateInviteLink {
+    pub name: String,
+    #[serde(default)]
+    #[serde(with = "ts_seconds_option")]
+    pub expires_at: Option<DateTime<Utc>>,
+    pub max_uses: Option<i32>,
+}
+
 #[derive(Deserialize)]
 pub struct LeaderboardUser {
     pub user: String,
 }
 
+#[derive(Deserialize, Default)]
+pub struct LeaderboardSettings {
+    pub time_window: Option<LeaderboardWindow>,
+    pub window_days: Option<i32>,
+    pub metric: Option<LeaderboardMetric>,
+    pub languages: Option<Vec<String>>,
+    pub project_pattern: Option<String>,
+    pub editor: Option<String>,
+    pub public: Option<bool>,
+    pub description: Option<String>,
+    pub avatar_url: Option<String>,
+    pub approval_required: Option<bool>,
+    pub exclude_manual: Option<bool>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateLeaderboard {
+    #[serde(flatten)]
+    pub leaderboard: LeaderboardName,
+    #[serde(flatten)]
+    pub settings: LeaderboardSettings,
+}
+
+/// Rolling windows need the amount of days, other windows ignore it
+fn validate_window(
+    time_window: LeaderboardWindow,
+    window_days: Option<i32>,
+) -> Result<Option<i32>, TimeError> {
+    if time_window != LeaderboardWindow::Rolling {
+        return Ok(None);
+    }
+
+    match window_days {
+        Some(days) if (1..=365).contains(&days) => Ok(Some(days)),
+        _ => Err(TimeError::InvalidLength(
+            "Rolling windows need window_days between 1 and 365".to_string(),
+        )),
+    }
+}
+
+/// An empty value removes the setting, a missing one keeps the current value
+fn validate_text(
+    value: &Option<String>,
+    current: Option<String>,
+    name: &str,
+    max_length: usize,
+) -> Result<Option<String>, TimeError> {
+    match value.as_deref().map(str::trim) {
+        None => Ok(current),
+        Some("") => Ok(None),
+        Some(value) if value.chars().count() <= max_length => Ok(Some(value.to_string())),
+        Some(_) => Err(TimeError::InvalidLength(format!(
+            "The {name} can be at most {max_length} characters long"
+        ))),
+    }
+}
+
+/// Applies the given settings on top of the current leaderboard configuration
+fn apply_settings(
+    settings: &LeaderboardSettings,
+    current: LeaderboardConfig,
+) -> Result<LeaderboardConfig, TimeError> {
+    let time_window = settings.time_window.unwrap_or(current.time_window);
+    let window_days = validate_window(time_window, settings.window_days.or(current.window_days))?;
+
+    let languages = match &settings.languages {
+        None => current.languages,
+        Some(languages) if languages.is_empty() => None,
+        Some(languages) => {
+            if languages.len() > 16
+                || languages
+                    .iter()
+                    .any(|l| l.trim().is_empty() || l.len() > 32)
+            {
+                return Err(TimeError::InvalidLength(
+                    "The language filter can have at most 16 languages of 1 to 32 characters"
+                        .to_string(),
+                ));
+            }
+            let mut languages: Vec<String> =
+                languages.iter().map(|l| l.trim().to_lowercase()).collect();
+            languages.sort();
+            languages.dedup();
+            Some(languages)
+        }
+    };
+
+    Ok(LeaderboardConfig {
+        time_window,
+        window_days,
+        metric: settings.metric.unwrap_or(current.metric),
+        languages,
+        project_pattern: validate_text(
+            &settings.project_pattern,
+            current.project_pattern,
+            "project filter",
+            64,
+        )?,
+        editor: validate_text(&settings.editor, current.editor, "editor filter", 32)?,
+        public: settings.public.unwrap_or(current.public),
+        description: validate_text(
+            &settings.description,
+            current.description,
+            "description",
+            512,
+        )?,
+        avatar_url: validate_text(&settings.avatar_url, current.avatar_url, "avatar url", 256)?
+            .map(|avatar_url| match url::Url::parse(&avatar_url) {
+                Ok(url) if ["http", "https"].contains(&url.scheme()) => Ok(avatar_url),
+                _ => Err(TimeError::BadAvatarUrl),
+            })
+            .transpose()?,
+        approval_required: settings
+            .approval_required
+            .unwrap_or(current.approval_required),
+        exclude_manual: settings.exclude_manual.unwrap_or(current.exclude_manual),
+    })
+}
+
 #[post("/leaderboards/create")]
 pub async fn create_leaderboard(
     creator: UserId,
-    body: Json<LeaderboardName>,
+    body: Json<CreateLeaderboard>,
     db: DatabaseWrapper,
 ) -> Result<impl Responder, TimeError> {
-    if !super::VALID_NAME_REGEX.is_match(&body.name) {
+    let name = &body.leaderboard.name;
+    if !super::VALID_NAME_REGEX.is_match(name) {
         return Err(TimeError::BadLeaderboardName);
     }
-    let lname = body.name.clone();
 
-    if db.get_leaderboard_id_by_name(lname).await.is_ok() {
+    let config = apply_settings(&body.settings, LeaderboardConfig::default())?;
+
+    if db.get_leaderboard_id_by_name(name.clone()).await.is_ok() {
         return Err(TimeError::LeaderboardExists);
     }
 
-    match db.create_leaderboard(creator.id, &body.name).await {
+    match db.create_leaderboard(creator.id, name, config).await {
         Ok(code) => Ok(web::Json(json!({ "invite_code": code }))),
         Err(e) => {
             error!("{}", e);
@@ -55,44 +200,117 @@ pub async fn create_leaderboard(
     }
 }
 
-#[get("/leaderboards/{name}")]
-pub async fn get_leaderboard(
-    user: UserId,
-    path: Path<(String,)>,
+#[get("/leaderboards/search")]
+pub async fn search_leaderboards(
     db: DatabaseWrapper,
+    search: Query<LeaderboardSearch>,
 ) -> Result<impl Responder, TimeError> {
-    let lid = db
-        .get_leaderboard_id_by_name(path.0.clone())
+    Ok(Json(
+        db.search_public_leaderboards(search.keyword.clone())
+            .await?,
+    ))
+}
+
+/// Leaderboards without an owner, e.g. when the owner deleted their account, can be
+/// managed by any of their admins
+async fn is_leaderboard_owner(
+    board: &Leaderboard,
+    uid: i32,
+    db: &DatabaseWrapper,
+) -> Result<bool, TimeError> {
+    match board.owner_id {
+        Some(owner_id) => Ok(owner_id == uid),
+        None => db.is_leaderboard_admin(uid, board.id).await,
+    }
+}
+
+/// Finds a leaderboard the user is allowed to view, returns whether they are a member of it
+async fn get_visible_leaderboard(
+    name: &str,
+    opt_user: &UserIdentityOptional,
+    db: &DatabaseWrapper,
+) -> Result<(Leaderboard, bool), TimeError> {
+    let board = db
+        .get_leaderboard_by_name(name.to_string())
         .await
         .map_err(|_| TimeError::LeaderboardNotFound)?;
 
-    if db.is_leaderboard_member(user.id, lid).await? {
-        let board = db.get_leaderboard(path.0.clone()).await?;
-        Ok(web::Json(board))
+    let is_member = match &opt_user.identity {
+        Some(user) => db.is_leaderboard_member(user.id, board.id).await?,
+        None => false,
+    };
+
+    if is_member || board.public {
+        Ok((board, is_member))
     } else {
         Err(TimeError::Unauthorized)
     }
 }
 
+/// Returns the members that can be shown to someone who isn't a member of the leaderboard:
+/// the owner, who made the leaderboard visible, and members with public accounts
+async fn visible_to_outsiders(
+    owner_id: Option<i32>,
+    members: Vec<i32>,
+    db: &DatabaseWrapper,
+) -> Result<Vec<i32>, TimeError> {
+    let mut visible = db.get_public_user_ids(members).await?;
+    visible.extend(owner_id);
+    Ok(visible)
+}
+
+#[get("/leaderboards/{name}")]
+pub async fn get_leaderboard(
+    opt_user: UserIdentityOptional,
+    path: Path<(String,)>,
+    db: DatabaseWrapper,
+) -> Result<impl Responder, TimeError> {
+    let (board, is_member) = get_visible_leaderboard(&path.0, &opt_user, &db).await?;
+
+    let timezone = opt_user
+        .identity
+        .as_ref()
+        .map_or(chrono_tz::Tz::UTC, UserIdentity::tz);
+    let owner_id = board.owner_id;
+    let mut board = db.get_leaderboard(board, timezone).await?;
+    if !is_member {
+        board.invite = None;
+        let members = board.members.iter().map(|member| member.id).collect();
+        let visible = visible_to_outsiders(owner_id, members, &db).await?;
+        board.members.retain(|member| visible.contains(&member.id));
+    }
+
+    Ok(web::Json(board))
+}
+
 #[delete("/leaderboards/{name}")]
 pub async fn delete_leaderboard(
     user: SecuredUserIdentity,
     path: Path<(String,)>,
     db: DatabaseWrapper,
 ) -> Result<impl Responder, TimeError> {
-    let lid = db
-        .get_leaderboard_id_by_name(path.0.clone())
+    let board = db
+        .get_leaderboard_by_name(path.0.clone())
         .await
         .map_err(|_| TimeError::LeaderboardNotFound)?;
 
-    if db.is_leaderboard_admin(user.identity.id, lid).await? {
-        db.delete_leaderboard(path.0.clone()).await?;
+    if db.is_leaderboard_admin(user.identity.id, board.id).await? {
+        db.delete_leaderboard(board.name).await?;
         Ok(HttpResponse::Ok().finish())
     } else {
         Err(TimeError::Unauthorized)
     }
 }
 
+fn join_response(join: LeaderboardJoin) -> HttpResponse {
+    match join {
+        LeaderboardJoin::Joined(leaderboard) => HttpResponse::Ok().json(leaderboard),
+        LeaderboardJoin::Requested(name) => {
+            HttpResponse::Accepted().json(json!({ "name": name, "pending": true }))
+        }
+    }
+}
+
 #[post("/leaderboards/join")]
 pub async fn join_leaderboard(
     user: UserId,
@@ -119,7 +337,142 @@ pub async fn join_leaderboard(
                 _ => e,
             })
         }
-        Ok(leaderboard) => Ok(web::Json(json!(leaderboard))),
+        Ok(join) => Ok(join_response(join)),
+    }
+}
+
+#[get("/leaderboards/{name}/seasons")]
+pub async fn get_seasons(
+    opt_user: UserIdentityOptional,
+    path: Path<(String,)>,
+    db: DatabaseWrapper,
+) -> Result<impl Responder, TimeError> {
+    let (board, is_member) = get_visible_leaderboard(&path.0, &opt_user, &db).await?;
+
+    let mut seasons = db.get_leaderboard_seasons(board.id).await?;
+    if !is_member {
+        let winners = seasons
+            .iter()
+            .filter_map(|season| season.winner.as_ref()?.user_id)
+            .collect();
+        let visible = visible_to_outsiders(board.owner_id, winners, &db).await?;
+        for season in &mut seasons {
+            if !season
+                .winner
+                .as_ref()
+                .and_then(|winner| winner.user_id)
+                .is_some_and(|winner| visible.contains(&winner))
+            {
+                season.winner = None;
+            }
+        }
+    }
+
+    Ok(web::Json(seasons))
+}
+
+#[get("/leaderboards/{name}/seasons/{period}")]
+pub async fn get_season(
+    opt_user: UserIdentityOptional,
+    path: Path<(String, String)>,
+    db: DatabaseWrapper,
+) -> Result<impl Responder, TimeError> {
+    let (board, is_member) = get_visible_leaderboard(&path.0, &opt_user, &db).await?;
+
+    let mut season = match db.get_leaderboard_season(board.id, path.1.clone()).await {
+        Err(TimeError::DieselError(diesel::result::Error::NotFound)) => {
+            return Err(TimeError::SeasonNotFound)
+        }
+        season => season?,
+    };
+    if !is_member {
+        let members = season
+            .members
+            .iter()
+            .filter_map(|member| member.user_id)
+            .collect();
+        let visible = visible_to_outsiders(board.owner_id, members, &db).await?;
+        season
+            .members
+            .retain(|member| member.user_id.is_some_and(|id| visible.contains(&id)));
+    }
+
+    Ok(web::Json(season))
+}
+
+/// Records the final rankings of the seasons that have ended before `now`
+pub async fn snapshot_leaderboard_seasons(db: &DatabaseWrapper, now: DateTime<Utc>) {
+    let boards = match db.get_all_leaderboards().await {
+        Ok(boards) => boards,
+        Err(e) => {
+            error!("Failed to load leaderboards for season snapshots: {}", e);
+            return;
+        }
+    };
+
+    for board in boards {
+        let last_recorded = match db.get_last_season_end(board.id).await {
+            Ok(last_recorded) => last_recorded,
+            Err(e) => {
+                error!(
+                    "Failed to load the seasons of leaderboard {}: {}",
+                    board.name, e
+                );
+                continue;
+            }
+        };
+
+        // NOTE: Seasons that ended while the server was down are recorded too, but not the
+        // ones from before seasons were tracked as the rankings use the current members
+        // and settings. The oldest seasons are recorded first so the rest can be caught up
+        // on the next run.
+        let recorded_until = last_recorded.map_or(board.seasons_since, |last_recorded| {
+            last_recorded.max(board.seasons_since)
+        });
+
+        let period = board.time_window.season_period();
+        let mut seasons = Vec::new();
+        let mut season = period.last_ended(now);
+        while season.2 > recorded_until {
+            let previous = period.last_ended(season.1);
+            seasons.push(season);
+            season = previous;
+        }
+
+        for (name, start, end) in seasons.into_iter().rev().take(MAX_SEASONS_PER_RUN) {
+            if let Err(e) = db.save_leaderboard_season(&board, name, start, end).await {
+                error!(
+                    "Failed to record the season of leaderboard {}: {}",
+                    board.name, e
+                );
+                break;
+            }
+        }
+    }
+}
+
+#[post("/leaderboards/{name}/join")]
+pub async fn join_public_leaderboard(
+    user: UserId,
+    path: Path<(String,)>,
+    db: DatabaseWrapper,
+) -> Result<impl Responder, TimeError> {
+    let board = db
+        .get_leaderboard_by_name(path.0.clone())
+        .await
+        .map_err(|_| TimeError::LeaderboardNotFound)?;
+
+    if !board.public {
+        return Err(TimeError::Unauthorized);
+    }
+
+    match db.add_leaderboard_member(user.id, board, None).await {
+        Err(TimeError::DieselError(diesel::result::Error::DatabaseError(
+            DatabaseErrorKind::UniqueViolation,
+            ..,
+        ))) => Err(TimeError::AlreadyMember),
+        Err(e) => Err(e),
+        Ok(join) => Ok(join_response(join)),
     }
 }
 
@@ -129,10 +482,15 @@ pub async fn leave_leaderboard(
     path: Path<(String,)>,
     db: DatabaseWrapper,
 ) -> Result<impl Responder, TimeError> {
-    let lid = db
-        .get_leaderboard_id_by_name(path.0.clone())
+    let board = db
+        .get_leaderboard_by_name(path.0.clone())
         .await
         .map_err(|_| TimeError::LeaderboardNotFound)?;
+    let lid = board.id;
+
+    if board.owner_id == Some(user.identity.id) {
+        return Err(TimeError::OwnerCannotLeave);
+    }
 
     if db.is_leaderboard_admin(user.identity.id, lid).await?
         && db.get_leaderboard_admin_count(lid).await? == 1
@@ -189,10 +547,11 @@ pub async fn demote_member(
     db: DatabaseWrapper,
     demotion: Json<LeaderboardUser>,
 ) -> Result<impl Responder, TimeError> {
-    let lid = db
-        .get_leaderboard_id_by_name(path.0.clone())
+    let board = db
+        .get_leaderboard_by_name(path.0.clone())
         .await
         .map_err(|_| TimeError::LeaderboardNotFound)?;
+    let lid = board.id;
 
     if db.is_leaderboard_admin(user.identity.id, lid).await? {
         let oldadmin = db
@@ -200,6 +559,10 @@ pub async fn demote_member(
             .await
             .map_err(|_| TimeError::UserNotFound)?;
 
+        if board.owner_id == Some(oldadmin.id) {
+            return Err(TimeError::Unauthorized);
+        }
+
         if db
             .demote_user_to_leaderboard_member(lid, oldadmin.id)
             .await?
@@ -221,10 +584,11 @@ pub async fn kick_member(
     db: DatabaseWrapper,
     kick: Json<LeaderboardUser>,
 ) -> Result<impl Responder, TimeError> {
-    let lid = db
-        .get_leaderboard_id_by_name(path.0.clone())
+    let board = db
+        .get_leaderboard_by_name(path.0.clone())
         .await
         .map_err(|_| TimeError::LeaderboardNotFound)?;
+    let lid = board.id;
 
     if db.is_leaderboard_admin(user.identity.id, lid).await? {
         let kmember = db
@@ -232,6 +596,10 @@ pub async fn kick_member(
             .await
             .map_err(|_| TimeError::UserNotFound)?;
 
+        if board.owner_id == Some(kmember.id) {
+            return Err(TimeError::Unauthorized);
+        }
+
         db.remove_user_from_leaderboard(lid, kmember.id)
             .await
             .map_err(|_| TimeError::NotMember)?;
@@ -259,3 +627,339 @@ pub async fn regenerate_invite(
         Err(TimeError::Unauthorized)
     }
 }
+
+#[post("/leaderboards/{name}/rename")]
+pub async fn rename_leaderboard(
+    user: SecuredUserIdentity,
+    path: Path<(String,)>,
+    db: DatabaseWrapper,
+    body: Json<LeaderboardName>,
+) -> Result<impl Responder, TimeError> {
+    let board = db
+        .get_leaderboard_by_name(path.0.clone())
+        .await
+        .map_err(|_| TimeError::LeaderboardNotFound)?;
+
+    if !db.is_leaderboard_admin(user.identity.id, board.id).await? {
+        return Err(TimeError::Unauthorized);
+    }
+
+    let name = body.name.trim();
+    if !super::VALID_NAME_REGEX.is_match(name) {
+        return Err(TimeError::BadLeaderboardName);
+    }
+
+    // NOTE: Names are unique case insensitively, but the case of the name can be changed
+    if matches!(db.get_leaderboard_id_by_name(name.to_string()).await, Ok(lid) if lid != board.id) {
+        return Err(TimeError::LeaderboardExists);
+    }
+
+    match db.rename_leaderboard(board.id, name.to_string()).await {
+        Err(TimeError::DieselError(diesel::result::Error::DatabaseError(
+            DatabaseErrorKind::UniqueViolation,
+            ..,
+        ))) => Err(TimeError::LeaderboardExists),
+        Err(e) => Err(e),
+        Ok(()) => Ok(web::Json(json!({ "name": name }))),
+    }
+}
+
+#[post("/leaderboards/{name}/transfer")]
+pub async fn transfer_ownership(
+    user: SecuredUserIdentity,
+    path: Path<(String,)>,
+    db: DatabaseWrapper,
+    body: Json<LeaderboardUser>,
+) -> Result<impl Responder, TimeError> {
+    let board = db
+        .get_leaderboard_by_name(path.0.clone())
+        .await
+        .map_err(|_| TimeError::LeaderboardNotFound)?;
+
+    if !is_leaderboard_owner(&board, user.identity.id, &db).await? {
+        return Err(TimeError::Unauthorized);
+    }
+
+    let new_owner = db
+        .get_user_by_name(body.user.clone())
+        .await
+        .map_err(|_| TimeError::UserNotFound)?;
+
+    if !db.is_leaderboard_member(new_owner.id, board.id).await? {
+        return Err(TimeError::NotMember);
+    }
+
+    db.transfer_leaderboard_ownership(board.id, new_owner.id)
+        .await?;
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[post("/leaderboards/{name}/invites")]
+pub async fn create_invite_link(
+    user: SecuredUserIdentity,
+    path: Path<(String,)>,
+    db: DatabaseWrapper,
+    body: Json<CreateInviteLink>,
+) -> Result<impl Responder, TimeError> {
+    let lid = db
+        .get_leaderboard_id_by_name(path.0.clone())
+        .await
+        .map_err(|_| TimeError::LeaderboardNotFound)?;
+
+    if !db.is_leaderboard_admin(user.identity.id, lid).await? {
+        return Err(TimeError::Unauthorized);
+    }
+
+    if !super::VALID_NAME_REGEX.is_match(&body.name) {
+        return Err(TimeError::InvalidLength(
+            "Invite name has to contain characters from [a-zA-Z0-9_] and has to be between 2 and 32 characters".to_string(),
+        ));
+    }
+
+    if body
+        .expires_at
+        .is_some_and(|expires_at| expires_at <= Utc::now())
+    {
+        return Err(TimeError::InvalidLength(
+            "Invites have to expire in the future".to_string(),
+        ));
+    }
+
+    if body
+        .max_uses
+        .is_some_and(|max_uses| !(1..=10000).contains(&max_uses))
+    {
+        return Err(TimeError::InvalidLength(
+            "Invites can have between 1 and 10000 uses".to_string(),
+        ));
+    }
+
+    let link = NewInviteLink {
+        leaderboard_id: lid,
+        name: body.name.clone(),
+        invite_code: crate::utils::generate_token(),
+        created_by: Some(user.identity.id),
+        expires_at: body.expires_at,
+        max_uses: body.max_uses,
+    };
+
+    match db.create_invite_link(link).await {
+        Err(TimeError::DieselError(diesel::result::Error::DatabaseError(
+            DatabaseErrorKind::UniqueViolation,
+            ..,
+        ))) => Err(TimeError::InviteExists),
+        Err(e) => Err(e),
+        Ok(link) => Ok(web::Json(link)),
+    }
+}
+
+#[get("/leaderboards/{name}/invites")]
+pub async fn get_invite_links(
+    user: UserIdentity,
+    path: Path<(String,)>,
+    db: DatabaseWrapper,
+) -> Result<impl Responder, TimeError> {
+    let lid = db
+        .get_leaderboard_id_by_name(path.0.clone())
+        .await
+        .map_err(|_| TimeError::LeaderboardNotFound)?;
+
+    if db.is_leaderboard_admin(user.id, lid).await? {
+        Ok(web::Json(db.get_invite_links(lid).await?))
+    } else {
+        Err(TimeError::Unauthorized)
+    }
+}
+
+#[delete("/leaderboards/{name}/invites/{invite}")]
+pub async fn delete_invite_link(
+    user: SecuredUserIdentity,
+    path: Path<(String, String)>,
+    db: DatabaseWrapper,
+) -> Result<impl Responder, TimeError> {
+    let lid = db
+        .get_leaderboard_id_by_name(path.0.clone())
+        .await
+        .map_err(|_| TimeError::LeaderboardNotFound)?;
+
+    if !db.is_leaderboard_admin(user.identity.id, lid).await? {
+        return Err(TimeError::Unauthorized);
+    }
+
+    if db.delete_invite_link(lid, path.1.clone()).await? {
+        Ok(HttpResponse::Ok().finish())
+    } else {
+        Err(TimeError::InviteNotFound)
+    }
+}
+
+#[get("/leaderboards/{name}/requests")]
+pub async fn get_join_requests(
+    user: UserIdentity,
+    path: Path<(String,)>,
+    db: DatabaseWrapper,
+) -> Result<impl Responder, TimeError> {
+    let lid = db
+        .get_leaderboard_id_by_name(path.0.clone())
+        .await
+        .map_err(|_| TimeError::LeaderboardNotFound)?;
+
+    if db.is_leaderboard_admin(user.id, lid).await? {
+        Ok(web::Json(db.get_join_requests(lid).await?))
+    } else {
+        Err(TimeError::Unauthorized)
+    }
+}
+
+async fn resolve_join_request(
+    user: SecuredUserIdentity,
+    name: String,
+    requester: String,
+    approve: bool,
+    db: DatabaseWrapper,
+) -> Result<HttpResponse, TimeError> {
+    let lid = db
+        .get_leaderboard_id_by_name(name)
+        .await
+        .map_err(|_| TimeError::LeaderboardNotFound)?;
+
+    if !db.is_leaderboard_admin(user.identity.id, lid).await? {
+        return Err(TimeError::Unauthorized);
+    }
+
+    let requester = db
+        .get_user_by_name(requester)
+        .await
+        .map_err(|_| TimeError::UserNotFound)?;
+
+    if db.resolve_join_request(lid, requester.id, approve).await? {
+        Ok(HttpResponse::Ok().finish())
+    } else {
+        Err(TimeError::JoinRequestNotFound)
+    }
+}
+
+#[post("/leaderboards/{name}/requests/approve")]
+pub async fn approve_join_request(
+    user: SecuredUserIdentity,
+    path: Path<(String,)>,
+    db: DatabaseWrapper,
+    body: Json<LeaderboardUser>,
+) -> Result<impl Responder, TimeError> {
+    resolve_join_request(user, path.0.clone(), body.user.clone(), true, db).await
+}
+
+#[post("/leaderboards/{name}/requests/reject")]
+pub async fn reject_join_request(
+    user: SecuredUserIdentity,
+    path: Path<(String,)>,
+    db: DatabaseWrapper,
+    body: Json<LeaderboardUser>,
+) -> Result<impl Responder, TimeError> {
+    resolve_join_request(user, path.0.clone(), body.user.clone(), false, db).await
+}
+
+#[post("/leaderboards/{name}/bans")]
+pub async fn ban_member(
+    user: SecuredUserIdentity,
+    path: Path<(String,)>,
+    db: DatabaseWrapper,
+    ban: Json<LeaderboardUser>,
+) -> Result<impl Responder, TimeError> {
+    let board = db
+        .get_leaderboard_by_name(path.0.clone())
+        .await
+        .map_err(|_| TimeError::LeaderboardNotFound)?;
+
+    if !db.is_leaderboard_admin(user.identity.id, board.id).await? {
+        return Err(TimeError::Unauthorized);
+    }
+
+    let banned = db
+        .get_user_by_name(ban.user.clone())
+        .await
+        .map_err(|_| TimeError::UserNotFound)?;
+
+    if banned.id == user.identity.id {
+        return Err(TimeError::CurrentUser);
+    }
+
+    if board.owner_id == Some(banned.id) {
+        return Err(TimeError::Unauthorized);
+    }
+
+    db.ban_leaderboard_member(board.id, banned.id, user.identity.id)
+        .await?;
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[get("/leaderboards/{name}/bans")]
+pub async fn get_bans(
+    user: UserIdentity,
+    path: Path<(String,)>,
+    db: DatabaseWrapper,
+) -> Result<impl Responder, TimeError> {
+    let lid = db
+        .get_leaderboard_id_by_name(path.0.clone())
+        .await
+        .map_err(|_| TimeError::LeaderboardNotFound)?;
+
+    if db.is_leaderboard_admin(user.id, lid).await? {
+        Ok(web::Json(db.get_leaderboard_bans(lid).await?))
+    } else {
+        Err(TimeError::Unauthorized)
+    }
+}
+
+#[delete("/leaderboards/{name}/bans/{user}")]
+pub async fn unban_member(
+    user: SecuredUserIdentity,
+    path: Path<(String, String)>,
+    db: DatabaseWrapper,
+) -> Result<impl Responder, TimeError> {
+    let lid = db
+        .get_leaderboard_id_by_name(path.0.clone())
+        .await
+        .map_err(|_| TimeError::LeaderboardNotFound)?;
+
+    if !db.is_leaderboard_admin(user.identity.id, lid).await? {
+        return Err(TimeError::Unauthorized);
+    }
+
+    let banned = db
+        .get_user_by_name(path.1.clone())
+        .await
+        .map_err(|_| TimeError::UserNotFound)?;
+
+    if db.unban_leaderboard_member(lid, banned.id).await? {
+        Ok(HttpResponse::Ok().finish())
+    } else {
+        Err(TimeError::BanNotFound)
+    }
+}
+
+#[post("/leaderboards/{name}/settings")]
+pub async fn change_settings(
+    user: SecuredUserIdentity,
+    path: Path<(String,)>,
+    db: DatabaseWrapper,
+    settings: Json<LeaderboardSettings>,
+) -> Result<impl Responder, TimeError> {
+    let board = db
+        .get_leaderboard_by_name(path.0.clone())
+        .await
+        .map_err(|_| TimeError::LeaderboardNotFound)?;
+
+    if db.is_leaderboard_admin(user.identity.id, board.id).await? {
+        let config = apply_settings(&settings, board.config())?;
+
+        db.update_leaderboard_config(board.id, config.clone())
+            .await?;
+
+        Ok(web::Json(config))
+    } else {
+        Err(TimeError::Unauthorized)
+    }
+}
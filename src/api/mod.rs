@@ -13,6 +13,7 @@ pub mod oauth;
 pub mod search;
 pub mod stats;
 pub mod users;
+pub mod wakatime;
 
 static VALID_NAME_REGEX: LazyLock<Regex> =
     LazyLock::new(|| Regex::new("^[[:word:]]{2,32}$").unwrap());
@@ -4,7 +4,7 @@ use actix_web::{
 };
 use serde_derive::Deserialize;
 
-use crate::{database::DatabaseWrapper, error::TimeError};
+use crate::{api::auth::UserIdentityOptional, database::DatabaseWrapper, error::TimeError};
 
 #[derive(Deserialize)]
 pub struct UserSearch {
@@ -14,8 +14,15 @@ pub struct UserSearch {
 //TODO: Maybe return small coding summary?
 #[get("/search/users")]
 pub async fn search_public_users(
+    opt_user: UserIdentityOptional,
     db: DatabaseWrapper,
     search: Query<UserSearch>,
 ) -> Result<impl Responder, TimeError> {
-    Ok(Json(db.search_public_users(search.keyword.clone()).await?))
+    Ok(Json(
+        db.search_public_users(
+            search.keyword.clone(),
+            opt_user.identity.map(|user| user.id),
+        )
+        .await?,
+    ))
 }
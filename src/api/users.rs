@@ -1,20 +1,36 @@
+use std::collections::HashMap;
+
 use actix_web::{
     error::*,
+    http::header::{self, ContentType},
     web::{self, Data, Path, Query},
-    HttpResponse, Responder,
+    HttpRequest, HttpResponse, Responder,
 };
-use chrono::{Duration, Local};
+use chrono::{Duration, NaiveDate, Utc};
+use futures::{stream, StreamExt, TryStreamExt};
 use serde_derive::Deserialize;
 
 use crate::{
-    api::{activity::HeartBeatMemoryStore, auth::UserIdentityOptional},
+    api::{
+        activity::{HeartBeatMemoryStore, SessionIdleTimeout},
+        auth::{SecuredUserIdentity, UserIdentityOptional},
+    },
     database::DatabaseWrapper,
     error::TimeError,
-    models::{CurrentActivity, PrivateLeaderboardMember, UserId, UserIdentity},
-    requests::DataRequest,
-    utils::group_by_language,
+    models::{
+        CodingActivity, CodingGoals, CurrentActivity, PrivateLeaderboardMember, Redaction, UserId,
+        UserIdentity,
+    },
+    requests::{
+        ActivityCursor, AggregateField, AggregateRequest, BlockRequest, DataFormat,
+        DataFormatRequest, DataRequest, GoalsRequest, StreakRequest,
+    },
+    utils::{days_ago_at_midnight, start_of_week},
 };
 
+const MAX_PAGE_SIZE: i64 = 1000;
+const DEFAULT_STREAK_MINUTES: i64 = 15;
+
 #[derive(Deserialize)]
 pub struct UserAuthentication {
     pub username: String,
@@ -49,6 +65,52 @@ pub async fn my_leaderboards(
     Ok(web::Json(db.get_user_leaderboards(user.id).await?))
 }
 
+#[get("/users/@me/blocks")]
+pub async fn get_blocked_users(
+    user: UserId,
+    db: DatabaseWrapper,
+) -> Result<impl Responder, TimeError> {
+    Ok(web::Json(db.get_blocked_users(user.id).await?))
+}
+
+#[post("/users/@me/blocks")]
+pub async fn block_user(
+    user: UserId,
+    body: web::Json<BlockRequest>,
+    db: DatabaseWrapper,
+) -> Result<impl Responder, TimeError> {
+    let blocked = db
+        .get_user_by_name(body.username.clone())
+        .await
+        .map_err(|_| TimeError::UserNotFound)?;
+
+    if blocked.id == user.id {
+        return Err(TimeError::CurrentUser);
+    }
+
+    db.block_user(user.id, blocked.id).await?;
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[delete("/users/@me/blocks/{username}")]
+pub async fn unblock_user(
+    user: UserId,
+    path: Path<(String,)>,
+    db: DatabaseWrapper,
+) -> Result<impl Responder, TimeError> {
+    let blocked = db
+        .get_user_by_name(path.0.clone())
+        .await
+        .map_err(|_| TimeError::UserNotFound)?;
+
+    if db.unblock_user(user.id, blocked.id).await? {
+        Ok(HttpResponse::Ok().finish())
+    } else {
+        Err(TimeError::UserNotFound)
+    }
+}
+
 #[delete("/users/@me/delete")]
 pub async fn delete_user(
     db: DatabaseWrapper,
@@ -64,110 +126,320 @@ pub async fn delete_user(
     Ok(HttpResponse::Ok().finish())
 }
 
+#[get("/users/@me/export")]
+pub async fn export_data(
+    user: SecuredUserIdentity,
+    db: DatabaseWrapper,
+) -> Result<impl Responder, TimeError> {
+    let user = user.identity;
+
+    let friends = db
+        .get_friends(user.id)
+        .await?
+        .into_iter()
+        .map(|f| f.username)
+        .collect::<Vec<_>>();
+
+    let leaderboards = db
+        .get_user_leaderboard_memberships(user.id)
+        .await?
+        .into_iter()
+        .map(|(name, admin)| json!({ "name": name, "admin": admin }))
+        .collect::<Vec<_>>();
+
+    let friend_visibility = db
+        .get_friend_visibilities(user.id)
+        .await?
+        .into_iter()
+        .map(|(friend, visibility)| {
+            json!({
+                "friend": friend,
+                "hide_project_names": visibility.hide_project_names,
+                "hide_hostnames": visibility.hide_hostnames,
+                "hide_editors": visibility.hide_editors,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let head = json!({
+        "user": {
+            "id": user.id,
+            "username": user.username,
+            "registration_time": user.registration_time,
+            "is_public": user.is_public,
+            "timezone": user.timezone,
+        },
+        "goals": db.get_coding_goals(user.id).await?,
+        "privacy": db.get_privacy_settings(user.id).await?,
+        "project_aliases": db.get_project_aliases(user.id).await?,
+        "friends": friends,
+        "friend_visibility": friend_visibility,
+        "blocked_users": db.get_blocked_users(user.id).await?,
+        "leaderboards": leaderboards,
+    })
+    .to_string();
+
+    // NOTE: The activities are streamed into the end of the object
+    let head = format!(
+        "{},\"activities\":[",
+        head.strip_suffix('}').expect("bug: impossible")
+    );
+
+    let activities = db
+        .stream_activity(DataRequest::default(), user.id)
+        .enumerate()
+        .map(|(i, activity)| {
+            let activity = serde_json::to_string(&activity?).map_err(ErrorInternalServerError)?;
+            Ok::<_, TimeError>(if i == 0 {
+                activity
+            } else {
+                format!(",{activity}")
+            })
+        });
+
+    let body = stream::once(async move { Ok(head) })
+        .chain(activities)
+        .chain(stream::once(async { Ok(String::from("]}")) }))
+        .map_ok(web::Bytes::from);
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .insert_header((
+            header::CONTENT_DISPOSITION,
+            "attachment; filename=\"testaustime-export.json\"",
+        ))
+        .streaming(body))
+}
+
 #[get("/users/{username}/activity/current")]
 pub async fn get_current_activity(
     path: Path<(String,)>,
     opt_user: UserIdentityOptional,
     db: DatabaseWrapper,
     heartbeats: Data<HeartBeatMemoryStore>,
+    idle_timeout: Data<SessionIdleTimeout>,
 ) -> Result<impl Responder, TimeError> {
-    let target_user = if let Some(user) = opt_user.identity {
-        if path.0 == "@me" {
-            user.id
+    let (target_user, redaction) = get_visible_user(&path.0, opt_user, &db).await?;
+
+    let curtime = Utc::now();
+
+    // NOTE: An idle session is taken out of the store before it is written, so that the
+    // reaper or another request can't write it too
+    if let Some((_, (inner_heartbeat, start, duration))) =
+        heartbeats.remove_if(&target_user.id, |_, (_, start, duration)| {
+            curtime.signed_duration_since(*start + *duration) > idle_timeout.0
+        })
+    {
+        db.add_activity(target_user.id, inner_heartbeat, start, duration)
+            .await
+            .map_err(ErrorInternalServerError)?;
+
+        return Err(TimeError::NotActive);
+    }
+
+    match heartbeats.get(&target_user.id).map(|s| s.to_owned()) {
+        Some((inner_heartbeat, start, duration)) => {
+            let current_heartbeat = CurrentActivity {
+                started: start,
+                duration: duration.num_seconds(),
+                heartbeat: redaction.redact_heartbeat(inner_heartbeat),
+            };
+            Ok(web::Json(Some(current_heartbeat)))
+        }
+        None => Err(TimeError::NotActive),
+    }
+}
+
+fn csv_field(field: Option<&str>) -> String {
+    match field {
+        Some(field) if field.contains([',', '"', '\n', '\r']) => {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        }
+        Some(field) => field.to_string(),
+        None => String::new(),
+    }
+}
+
+fn activity_to_csv(activity: &CodingActivity) -> String {
+    format!(
+        "{},{},{},{},{},{},{},{}\n",
+        activity.id,
+        activity.start_time.format("%Y-%m-%dT%H:%M:%S%.fZ"),
+        activity.duration,
+        csv_field(activity.project_name.as_deref()),
+        csv_field(activity.language.as_deref()),
+        csv_field(activity.editor_name.as_deref()),
+        csv_field(activity.hostname.as_deref()),
+        activity.source.as_str(),
+    )
+}
+
+/// Resolves the user whose data is requested, checking that the requester
+/// is allowed to see it. Users without authentication can only see public users.
+/// Returns what is hidden from the requester.
+async fn get_visible_user(
+    username: &str,
+    opt_user: UserIdentityOptional,
+    db: &DatabaseWrapper,
+) -> Result<(UserIdentity, Redaction), TimeError> {
+    if let Some(user) = opt_user.identity {
+        if username == "@me" {
+            Ok((user, Redaction::default()))
         } else {
             let target_user = db
-                .get_user_by_name(path.0.clone())
+                .get_user_by_name(username.to_string())
                 .await
                 .map_err(|_| TimeError::UserNotFound)?;
 
-            if target_user.id == user.id
-                || target_user.is_public
-                || db.are_friends(user.id, target_user.id).await?
-            {
-                target_user.id
+            if target_user.id == user.id {
+                return Ok((user, Redaction::default()));
+            }
+
+            if db.is_blocked_either_way(user.id, target_user.id).await? {
+                return Err(TimeError::UserNotFound);
+            }
+
+            if db.are_friends(user.id, target_user.id).await? {
+                let redaction = db.get_redaction(target_user.id, Some(user.id)).await?;
+                Ok((target_user, redaction))
+            } else if target_user.is_public {
+                let redaction = db.get_redaction(target_user.id, None).await?;
+                Ok((target_user, redaction))
             } else {
-                return Err(TimeError::Unauthorized);
+                Err(TimeError::Unauthorized)
             }
         }
     } else {
         let target_user = db
-            .get_user_by_name(path.0.clone())
+            .get_user_by_name(username.to_string())
             .await
             .map_err(|_| TimeError::UserNotFound)?;
 
         if target_user.is_public {
-            target_user.id
+            let redaction = db.get_redaction(target_user.id, None).await?;
+            Ok((target_user, redaction))
         } else {
-            return Err(TimeError::UserNotFound);
+            Err(TimeError::UserNotFound)
         }
-    };
-
-    match heartbeats.get(&target_user) {
-        Some(heartbeat) => {
-            let (inner_heartbeat, start, duration) = heartbeat.to_owned();
-            drop(heartbeat);
-            let curtime = Local::now().naive_local();
-            if curtime.signed_duration_since(start + duration) > Duration::seconds(900) {
-                db.add_activity(target_user, inner_heartbeat, start, duration)
-                    .await
-                    .map_err(ErrorInternalServerError)?;
-
-                heartbeats.remove(&target_user);
-                Err(TimeError::NotActive)
-            } else {
-                let current_heartbeat = CurrentActivity {
-                    started: start,
-                    duration: duration.num_seconds(),
-                    heartbeat: inner_heartbeat,
-                };
-                Ok(web::Json(Some(current_heartbeat)))
-            }
-        }
-        None => Err(TimeError::NotActive),
     }
 }
 
 #[get("/users/{username}/activity/data")]
 pub async fn get_activities(
     Query(data): Query<DataRequest>,
+    Query(format): Query<DataFormatRequest>,
     path: Path<(String,)>,
     opt_user: UserIdentityOptional,
     db: DatabaseWrapper,
+    request: HttpRequest,
 ) -> Result<impl Responder, TimeError> {
-    let Some(user) = opt_user.identity else {
-        let target_user = db
-            .get_user_by_name(path.0.clone())
-            .await
-            .map_err(|_| TimeError::UserNotFound)?;
+    let (target_user, redaction) = get_visible_user(&path.0, opt_user, &db).await?;
 
-        if target_user.is_public {
-            return Ok(web::Json(db.get_activity(data, target_user.id).await?));
-        } else {
-            return Err(TimeError::UserNotFound);
-        };
-    };
+    if redaction.hides_filter(&data) {
+        return Err(TimeError::Unauthorized);
+    }
 
-    let data = if path.0 == "@me" {
-        db.get_activity(data, user.id).await?
-    } else {
-        //FIXME: This is technically not required when the username equals the username of the
-        //authenticated user
-        let target_user = db
-            .get_user_by_name(path.0.clone())
-            .await
-            .map_err(|_| TimeError::UserNotFound)?;
+    if data
+        .limit
+        .is_some_and(|limit| !(1..=MAX_PAGE_SIZE).contains(&limit))
+    {
+        return Err(TimeError::InvalidLength(format!(
+            "Limit has to be between 1 and {MAX_PAGE_SIZE}"
+        )));
+    }
 
-        if target_user.id == user.id
-            || target_user.is_public
-            || db.are_friends(user.id, target_user.id).await?
-        {
-            db.get_activity(data, target_user.id).await?
+    let paginate = format.paginate;
+    let format = format.format.unwrap_or_else(|| {
+        let accept = request
+            .headers()
+            .get(header::ACCEPT)
+            .and_then(|accept| accept.to_str().ok())
+            .unwrap_or_default();
+
+        if accept.contains("text/csv") {
+            DataFormat::Csv
+        } else if accept.contains("application/x-ndjson") {
+            DataFormat::Ndjson
         } else {
-            return Err(TimeError::Unauthorized);
+            DataFormat::Json
         }
-    };
+    });
 
-    Ok(web::Json(data))
+    match format {
+        DataFormat::Json => {
+            if !paginate {
+                let activities = db
+                    .get_activity(data, target_user.id)
+                    .await?
+                    .into_iter()
+                    .map(|activity| redaction.redact_activity(activity))
+                    .collect::<Vec<_>>();
+                return Ok(HttpResponse::Ok().json(activities));
+            }
+
+            let limit = data.limit.unwrap_or(MAX_PAGE_SIZE);
+
+            // NOTE: One extra activity is loaded to know if there is a next page
+            let mut activities = db
+                .get_activity(
+                    DataRequest {
+                        limit: Some(limit + 1),
+                        ..data
+                    },
+                    target_user.id,
+                )
+                .await?;
+
+            let next_cursor = if activities.len() as i64 > limit {
+                activities.truncate(limit as usize);
+                activities
+                    .last()
+                    .map(|activity| ActivityCursor::from(activity).to_string())
+            } else {
+                None
+            };
+
+            let activities = activities
+                .into_iter()
+                .map(|activity| redaction.redact_activity(activity))
+                .collect::<Vec<_>>();
+
+            Ok(HttpResponse::Ok().json(json!({
+                "data": activities,
+                "next_cursor": next_cursor,
+            })))
+        }
+        DataFormat::Csv => {
+            let header = String::from(
+                "id,start_time,duration,project_name,language,editor_name,hostname,source\n",
+            );
+            let rows = db
+                .stream_activity(data, target_user.id)
+                .map_ok(move |activity| activity_to_csv(&redaction.redact_activity(activity)));
+
+            let body = stream::once(async move { Ok(header) })
+                .chain(rows)
+                .map_ok(web::Bytes::from);
+
+            Ok(HttpResponse::Ok()
+                .content_type("text/csv; charset=utf-8")
+                .streaming(body))
+        }
+        DataFormat::Ndjson => {
+            let body = db
+                .stream_activity(data, target_user.id)
+                .map(move |activity| {
+                    let mut line = serde_json::to_vec(&redaction.redact_activity(activity?))
+                        .map_err(ErrorInternalServerError)?;
+                    line.push(b'\n');
+                    Ok::<_, TimeError>(web::Bytes::from(line))
+                });
+
+            Ok(HttpResponse::Ok()
+                .content_type("application/x-ndjson")
+                .streaming(body))
+        }
+    }
 }
 
 #[get("/users/{username}/activity/summary")]
@@ -176,65 +448,217 @@ pub async fn get_activity_summary(
     opt_user: UserIdentityOptional,
     db: DatabaseWrapper,
 ) -> Result<impl Responder, TimeError> {
-    let data = if let Some(user) = opt_user.identity {
-        if path.0 == "@me" {
-            db.get_all_activity(user.id).await?
-        } else {
-            let target_user = db
-                .get_user_by_name(path.0.clone())
-                .await
-                .map_err(|_| TimeError::UserNotFound)?;
+    let (target_user, _) = get_visible_user(&path.0, opt_user, &db).await?;
+    let timezone = target_user.tz();
+
+    let mut summary = serde_json::Map::new();
+    for (key, from) in [
+        ("last_week", Some(days_ago_at_midnight(timezone, 7))),
+        ("last_month", Some(days_ago_at_midnight(timezone, 30))),
+        ("all_time", None),
+    ] {
+        let request = AggregateRequest {
+            from,
+            group_by: vec![AggregateField::Language],
+            ..Default::default()
+        };
+        let languages = db
+            .get_activity_aggregate(target_user.id, &request, timezone)
+            .await?
+            .into_iter()
+            .map(|a| (a.language.unwrap_or_else(|| String::from("none")), a.total))
+            .collect::<HashMap<_, _>>();
 
-            if target_user.id == user.id
-                || target_user.is_public
-                || db.are_friends(user.id, target_user.id).await?
-            {
-                db.get_all_activity(target_user.id).await?
+        summary.insert(
+            key.to_string(),
+            json!({
+                "languages": languages,
+                "total": languages.values().sum::<i64>(),
+            }),
+        );
+    }
+
+    Ok(web::Json(summary))
+}
+
+#[get("/users/{username}/activity/aggregate")]
+pub async fn get_activity_aggregate(
+    Query(request): Query<AggregateRequest>,
+    path: Path<(String,)>,
+    opt_user: UserIdentityOptional,
+    db: DatabaseWrapper,
+) -> Result<impl Responder, TimeError> {
+    let (target_user, redaction) = get_visible_user(&path.0, opt_user, &db).await?;
+    let timezone = target_user.tz();
+
+    // NOTE: Hidden fields are not grouped by, they are shown as placeholders
+    let query = AggregateRequest {
+        from: request.from,
+        to: request.to,
+        group_by: request
+            .group_by
+            .iter()
+            .copied()
+            .filter(|field| !redaction.hides(*field))
+            .collect(),
+        bucket: request.bucket,
+    };
+
+    // NOTE: Private projects are merged into one entry
+    let mut data: Vec<serde_json::Map<String, serde_json::Value>> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    for aggregate in db
+        .get_activity_aggregate(target_user.id, &query, timezone)
+        .await?
+    {
+        let mut entry = serde_json::Map::new();
+        if request.bucket.is_some() {
+            entry.insert("bucket".to_string(), json!(aggregate.bucket));
+        }
+        for field in &request.group_by {
+            let value = if redaction.hides(*field) {
+                Some(Redaction::placeholder(*field).to_string())
             } else {
-                return Err(TimeError::Unauthorized);
+                let value = match field {
+                    AggregateField::Project => &aggregate.project_name,
+                    AggregateField::Language => &aggregate.language,
+                    AggregateField::Editor => &aggregate.editor_name,
+                    AggregateField::Hostname => &aggregate.hostname,
+                };
+                redaction.redact(*field, value.clone())
+            };
+            entry.insert(field.name().to_string(), json!(value));
+        }
+
+        let key = serde_json::Value::Object(entry.clone()).to_string();
+        match positions.get(&key) {
+            Some(&position) => {
+                let total = &mut data[position]["total"];
+                *total = json!(total.as_i64().unwrap_or_default() + aggregate.total);
+            }
+            None => {
+                entry.insert("total".to_string(), json!(aggregate.total));
+                positions.insert(key, data.len());
+                data.push(entry);
             }
         }
-    } else {
-        let target_user = db
-            .get_user_by_name(path.0.clone())
-            .await
-            .map_err(|_| TimeError::UserNotFound)?;
+    }
 
-        if target_user.is_public {
-            db.get_all_activity(target_user.id).await?
+    Ok(web::Json(data))
+}
+
+#[get("/users/{username}/goals")]
+pub async fn get_goals(
+    path: Path<(String,)>,
+    opt_user: UserIdentityOptional,
+    db: DatabaseWrapper,
+) -> Result<impl Responder, TimeError> {
+    let (target_user, _) = get_visible_user(&path.0, opt_user, &db).await?;
+    let timezone = target_user.tz();
+
+    let goals = db.get_coding_goals(target_user.id).await?;
+
+    let today_time = db
+        .get_user_coding_time_since(target_user.id, days_ago_at_midnight(timezone, 0))
+        .await?;
+    let week_time = db
+        .get_user_coding_time_since(target_user.id, start_of_week(timezone))
+        .await?;
+
+    Ok(web::Json(json!({
+        "daily_goal": goals.daily_goal,
+        "weekly_goal": goals.weekly_goal,
+        "today": today_time,
+        "this_week": week_time,
+        "daily_goal_reached": goals.daily_goal.map(|goal| today_time >= goal),
+        "weekly_goal_reached": goals.weekly_goal.map(|goal| week_time >= goal),
+    })))
+}
+
+#[post("/users/@me/goals")]
+pub async fn set_goals(
+    user: SecuredUserIdentity,
+    body: web::Json<GoalsRequest>,
+    db: DatabaseWrapper,
+) -> Result<impl Responder, TimeError> {
+    if body
+        .daily_goal
+        .is_some_and(|goal| !(1..=24 * 60 * 60).contains(&goal))
+    {
+        return Err(TimeError::InvalidLength(
+            "Daily goal has to be between 1 second and 24 hours".to_string(),
+        ));
+    }
+    if body
+        .weekly_goal
+        .is_some_and(|goal| !(1..=7 * 24 * 60 * 60).contains(&goal))
+    {
+        return Err(TimeError::InvalidLength(
+            "Weekly goal has to be between 1 second and 7 days".to_string(),
+        ));
+    }
+
+    db.set_coding_goals(CodingGoals {
+        user_id: user.identity.id,
+        daily_goal: body.daily_goal,
+        weekly_goal: body.weekly_goal,
+    })
+    .await?;
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// Returns the current and the longest streak of consecutive days. The current streak
+/// is kept alive if the last day was yesterday, because today isn't over yet.
+fn count_streaks(days: &[NaiveDate], today: NaiveDate) -> (usize, usize) {
+    let mut longest = 0;
+    let mut streak = 0;
+    let mut previous: Option<NaiveDate> = None;
+
+    for &day in days {
+        streak = if previous.is_some_and(|previous| day - previous == Duration::days(1)) {
+            streak + 1
         } else {
-            return Err(TimeError::UserNotFound);
-        }
+            1
+        };
+        longest = longest.max(streak);
+        previous = Some(day);
+    }
+
+    let current = if previous.is_some_and(|previous| today - previous <= Duration::days(1)) {
+        streak
+    } else {
+        0
     };
 
-    //FIXME: This does a lot of unnecessary calculations
-    let now = Local::now().naive_local();
+    (current, longest)
+}
 
-    let all_time = group_by_language(data.clone().into_iter());
-    let last_month = group_by_language(
-        data.clone()
-            .into_iter()
-            .filter(|d| now.signed_duration_since(d.start_time) < Duration::days(30)),
-    );
-    let last_week = group_by_language(
-        data.into_iter()
-            .filter(|d| now.signed_duration_since(d.start_time) < Duration::days(7)),
-    );
+#[get("/users/{username}/streak")]
+pub async fn get_streak(
+    Query(request): Query<StreakRequest>,
+    path: Path<(String,)>,
+    opt_user: UserIdentityOptional,
+    db: DatabaseWrapper,
+) -> Result<impl Responder, TimeError> {
+    let min_minutes = request.min_minutes.unwrap_or(DEFAULT_STREAK_MINUTES);
+    if !(1..=24 * 60).contains(&min_minutes) {
+        return Err(TimeError::InvalidLength(
+            "Minimum minutes has to be between 1 and 1440".to_string(),
+        ));
+    }
 
-    let langs = serde_json::json!({
-        "last_week": {
-            "languages": last_week,
-            "total": last_week.values().sum::<i32>(),
-        },
-        "last_month": {
-            "languages": last_month,
-            "total": last_month.values().sum::<i32>(),
-        },
-        "all_time": {
-            "languages": all_time,
-            "total": all_time.values().sum::<i32>(),
-        },
-    });
+    let (target_user, _) = get_visible_user(&path.0, opt_user, &db).await?;
+    let timezone = target_user.tz();
+
+    let days = db
+        .get_coding_days(target_user.id, min_minutes * 60, timezone)
+        .await?;
+    let (current, longest) = count_streaks(&days, Utc::now().with_timezone(&timezone).date_naive());
 
-    Ok(web::Json(langs))
+    Ok(web::Json(json!({
+        "current": current,
+        "longest": longest,
+        "min_minutes": min_minutes,
+    })))
 }
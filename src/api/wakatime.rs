@@ -0,0 +1,360 @@
+use std::collections::HashSet;
+
+use actix_web::{
+    error::*,
+    web::{self, Data, Json, Query},
+    HttpRequest, HttpResponse, Responder,
+};
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+use futures_util::StreamExt;
+use serde::Serialize;
+
+use crate::{
+    api::{
+        activity::{
+            apply_heartbeats, get_alias_rules, validate_heartbeat, HeartBeatMemoryStore,
+            ProjectAliasCache, SessionIdleTimeout, MAX_BATCH_SIZE,
+        },
+        auth::{SecuredUserIdentity, WakaTimeUserId},
+    },
+    database::DatabaseWrapper,
+    error::TimeError,
+    models::{ActivitySource, NewCodingActivity, ProjectAliasRules},
+    requests::{
+        normalize_project_name, HeartBeat, ImportRequest, WakaTimeDay, WakaTimeDuration,
+        WakaTimeExport, WakaTimeHeartBeat,
+    },
+    utils::midnight,
+};
+
+const MAX_IMPORT_SIZE: usize = 64 * 1024 * 1024;
+const MAX_DAY_SECONDS: i64 = 24 * 60 * 60;
+
+/// Parses the editor from a WakaTime user agent, for example
+/// `wakatime/v1.73.0 (linux-5.15.0) go1.20.2 vscode/1.77.0 vscode-wakatime/24.0.10`
+fn editor_from_user_agent(user_agent: &str) -> Option<String> {
+    let plugin = user_agent.split_whitespace().last()?;
+    let name = plugin.split('/').next()?;
+
+    Some(name.strip_suffix("-wakatime").unwrap_or(name).to_string())
+}
+
+/// Shortens the value to at most `max_len` bytes without splitting characters
+fn truncate(value: &str, max_len: usize) -> String {
+    let mut end = value.len().min(max_len);
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+    value[..end].to_string()
+}
+
+fn convert_heartbeat(
+    heartbeat: WakaTimeHeartBeat,
+    request: &HttpRequest,
+    aliases: &ProjectAliasRules,
+) -> Result<(HeartBeat, DateTime<Utc>), TimeError> {
+    if !heartbeat.time.is_finite() {
+        return Err(TimeError::InvalidTimestamp);
+    }
+
+    let time = Utc
+        .timestamp_millis_opt((heartbeat.time * 1000.0) as i64)
+        .single()
+        .ok_or(TimeError::InvalidTimestamp)?;
+
+    if time > Utc::now() {
+        return Err(TimeError::FutureHeartBeat);
+    }
+
+    let header = |name| {
+        request
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+    };
+
+    // NOTE: The editor and hostname are set by the WakaTime client instead of the user,
+    // so they are shortened instead of rejecting the heartbeat when they are too long
+    let converted = HeartBeat {
+        project_name: heartbeat
+            .project
+            .map(|project| aliases.apply(normalize_project_name(project))),
+        language: heartbeat.language,
+        editor_name: heartbeat
+            .user_agent
+            .as_deref()
+            .or_else(|| header("User-Agent"))
+            .and_then(editor_from_user_agent)
+            .map(|editor| truncate(&editor, 32)),
+        hostname: header("X-Machine-Name").map(|hostname| truncate(hostname, 32)),
+    };
+
+    validate_heartbeat(&converted)?;
+
+    Ok((converted, time))
+}
+
+#[post("/api/v1/users/current/heartbeats")]
+pub async fn send_heartbeat(
+    user: WakaTimeUserId,
+    body: Json<WakaTimeHeartBeat>,
+    request: HttpRequest,
+    db: DatabaseWrapper,
+    heartbeats: Data<HeartBeatMemoryStore>,
+    idle_timeout: Data<SessionIdleTimeout>,
+    alias_cache: Data<ProjectAliasCache>,
+) -> Result<impl Responder, TimeError> {
+    let aliases = get_alias_rules(user.id, &db, &alias_cache).await?;
+    let (heartbeat, time) = convert_heartbeat(body.into_inner(), &request, &aliases)?;
+
+    apply_heartbeats(
+        user.id,
+        vec![(heartbeat.clone(), time)],
+        idle_timeout.0,
+        &db,
+        &heartbeats,
+    )
+    .await?;
+
+    Ok(HttpResponse::Created().json(json!({ "data": heartbeat })))
+}
+
+#[post("/api/v1/users/current/heartbeats.bulk")]
+pub async fn send_heartbeats_bulk(
+    user: WakaTimeUserId,
+    body: Json<Vec<WakaTimeHeartBeat>>,
+    request: HttpRequest,
+    db: DatabaseWrapper,
+    heartbeats: Data<HeartBeatMemoryStore>,
+    idle_timeout: Data<SessionIdleTimeout>,
+    alias_cache: Data<ProjectAliasCache>,
+) -> Result<impl Responder, TimeError> {
+    if body.len() > MAX_BATCH_SIZE {
+        return Err(TimeError::InvalidLength(format!(
+            "Batch is over {MAX_BATCH_SIZE} heartbeats"
+        )));
+    }
+
+    let aliases = get_alias_rules(user.id, &db, &alias_cache).await?;
+    let mut responses = Vec::new();
+    let mut valid = Vec::new();
+
+    for heartbeat in body.into_inner() {
+        match convert_heartbeat(heartbeat, &request, &aliases) {
+            Ok((heartbeat, time)) => {
+                responses.push(json!([{ "data": heartbeat }, 201]));
+                valid.push((heartbeat, time));
+            }
+            Err(e) => responses.push(json!([{ "error": e.to_string() }, 400])),
+        }
+    }
+
+    apply_heartbeats(user.id, valid, idle_timeout.0, &db, &heartbeats).await?;
+
+    Ok(HttpResponse::Created().json(json!({ "responses": responses })))
+}
+
+fn most_used(durations: &[WakaTimeDuration]) -> Option<String> {
+    durations
+        .iter()
+        .max_by(|a, b| a.total_seconds.total_cmp(&b.total_seconds))
+        .map(|d| d.name.clone())
+}
+
+/// Converts a day of a WakaTime export into activities. WakaTime only stores the
+/// total time per project and language, so the most used editor and machine are used
+/// and the activities are laid out back to back from midnight in the timezone of the user.
+/// Rows that don't fit in the database are returned as errors so the rest of the day can
+/// still be imported, but a day with more activity than fits in it is rejected completely.
+fn convert_day(
+    user_id: i32,
+    timezone: Tz,
+    day: WakaTimeDay,
+    aliases: &ProjectAliasRules,
+) -> Vec<Result<NewCodingActivity, TimeError>> {
+    let day_start = midnight(timezone, day.date);
+    // NOTE: Days are shorter than 24 hours when daylight saving time starts
+    let day_length = day
+        .date
+        .succ_opt()
+        .map_or(MAX_DAY_SECONDS, |next| {
+            (midnight(timezone, next) - day_start).num_seconds()
+        })
+        .min(MAX_DAY_SECONDS);
+
+    let mut durations = Vec::new();
+    if day.projects.is_empty() {
+        let editor = most_used(&day.editors);
+        let hostname = most_used(&day.machines);
+        for language in day.languages {
+            let heartbeat = HeartBeat {
+                project_name: None,
+                language: Some(language.name),
+                editor_name: editor.clone(),
+                hostname: hostname.clone(),
+            };
+            durations.push((heartbeat, language.total_seconds));
+        }
+    }
+
+    for project in day.projects {
+        let project_name = Some(aliases.apply(normalize_project_name(project.name)));
+        let editor = most_used(&project.editors).or_else(|| most_used(&day.editors));
+        let hostname = most_used(&project.machines).or_else(|| most_used(&day.machines));
+
+        if project.languages.is_empty() {
+            let heartbeat = HeartBeat {
+                project_name,
+                language: None,
+                editor_name: editor,
+                hostname,
+            };
+            let total = project.grand_total.map_or(0.0, |t| t.total_seconds);
+            durations.push((heartbeat, total));
+        } else {
+            for language in project.languages {
+                let heartbeat = HeartBeat {
+                    project_name: project_name.clone(),
+                    language: Some(language.name),
+                    editor_name: editor.clone(),
+                    hostname: hostname.clone(),
+                };
+                durations.push((heartbeat, language.total_seconds));
+            }
+        }
+    }
+
+    let rows = durations
+        .into_iter()
+        .filter(|(_, seconds)| *seconds >= 1.0)
+        .map(|(heartbeat, seconds)| {
+            validate_heartbeat(&heartbeat)?;
+
+            if seconds.round() > day_length as f64 {
+                return Err(TimeError::InvalidLength(
+                    "Activity is longer than the day".to_string(),
+                ));
+            }
+
+            Ok((heartbeat, seconds.round() as i32))
+        })
+        .collect::<Vec<_>>();
+
+    let total = rows
+        .iter()
+        .flatten()
+        .map(|(_, duration)| *duration as i64)
+        .sum::<i64>();
+
+    if total > day_length {
+        let mut errors = rows
+            .into_iter()
+            .filter_map(Result::err)
+            .map(Err)
+            .collect::<Vec<_>>();
+        errors.push(Err(TimeError::InvalidLength(
+            "The day has more activity than fits in it".to_string(),
+        )));
+        return errors;
+    }
+
+    let mut start_time = day_start;
+    rows.into_iter()
+        .map(|row| {
+            let (heartbeat, duration) = row?;
+            let activity = NewCodingActivity {
+                user_id,
+                start_time,
+                duration,
+                project_name: heartbeat.project_name,
+                language: heartbeat.language,
+                editor_name: heartbeat.editor_name,
+                hostname: heartbeat.hostname,
+                source: ActivitySource::Editor,
+            };
+            start_time += Duration::seconds(duration.into());
+            Ok(activity)
+        })
+        .collect()
+}
+
+/// A row of the export that was left out of the import
+#[derive(Serialize)]
+struct SkippedActivity {
+    date: NaiveDate,
+    reason: String,
+}
+
+#[post("/users/@me/import/wakatime")]
+pub async fn import(
+    user: SecuredUserIdentity,
+    Query(request): Query<ImportRequest>,
+    mut payload: web::Payload,
+    db: DatabaseWrapper,
+    alias_cache: Data<ProjectAliasCache>,
+) -> Result<impl Responder, TimeError> {
+    let mut body = web::BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk.map_err(ErrorBadRequest)?;
+        if body.len() + chunk.len() > MAX_IMPORT_SIZE {
+            return Err(TimeError::InvalidLength(
+                "Import is over 64 MiB".to_string(),
+            ));
+        }
+        body.extend_from_slice(&chunk);
+    }
+
+    let export = serde_json::from_slice::<WakaTimeExport>(&body).map_err(ErrorBadRequest)?;
+
+    let aliases = get_alias_rules(user.identity.id, &db, &alias_cache).await?;
+    let timezone = user.identity.tz();
+
+    // NOTE: Days which already have activity are skipped, so running the same import again
+    // doesn't count the time twice
+    let existing_days = db
+        .get_coding_days(user.identity.id, 0, timezone)
+        .await?
+        .into_iter()
+        .collect::<HashSet<_>>();
+
+    let mut activities = Vec::new();
+    let mut skipped_days = 0;
+    let mut skipped = Vec::new();
+    for day in export.days {
+        if existing_days.contains(&day.date) {
+            skipped_days += 1;
+            skipped.push(SkippedActivity {
+                date: day.date,
+                reason: "The day already has activity".to_string(),
+            });
+            continue;
+        }
+
+        let date = day.date;
+        for activity in convert_day(user.identity.id, timezone, day, &aliases) {
+            match activity {
+                Ok(activity) => activities.push(activity),
+                Err(e) => skipped.push(SkippedActivity {
+                    date,
+                    reason: e.to_string(),
+                }),
+            }
+        }
+    }
+
+    let activity_count = activities.len();
+    let total_seconds = activities.iter().map(|a| a.duration as i64).sum::<i64>();
+
+    if !request.dry_run {
+        db.import_activities(activities).await?;
+    }
+
+    Ok(web::Json(json!({
+        "dry_run": request.dry_run,
+        "activities": activity_count,
+        "total_seconds": total_seconds,
+        "skipped_days": skipped_days,
+        "skipped": skipped,
+    })))
+}
@@ -70,7 +70,12 @@ where
 
         Box::pin(async move {
             if let Some(auth) = auth {
-                if let Some(token) = auth.to_str().unwrap().trim().strip_prefix("Bearer ") {
+                // NOTE: Headers that aren't valid ASCII are treated like missing ones
+                if let Some(token) = auth
+                    .to_str()
+                    .ok()
+                    .and_then(|auth| auth.trim().strip_prefix("Bearer "))
+                {
                     let db = db.await.map_err(ErrorInternalServerError)?;
 
                     if let Ok(secured_access_instance) = secured_access_storage.get(token).clone() {
@@ -1,11 +1,17 @@
+use std::sync::Arc;
+
 use chrono::{prelude::*, Duration};
-use diesel::prelude::*;
+use chrono_tz::Tz;
+use diesel::{pg::Pg, prelude::*};
 use diesel_async::RunQueryDsl;
+use futures::{channel::mpsc, SinkExt};
+use futures_util::TryStreamExt;
 
 use crate::{
     error::TimeError,
     models::*,
-    requests::{DataRequest, HeartBeat},
+    requests::{AggregateField, AggregateRequest, DataRequest, HeartBeat, SortOrder},
+    utils::days_ago_at_midnight,
 };
 
 impl super::DatabaseWrapper {
@@ -13,7 +19,7 @@ impl super::DatabaseWrapper {
         &self,
         updated_user_id: i32,
         heartbeat: HeartBeat,
-        ctx_start_time: NaiveDateTime,
+        ctx_start_time: DateTime<Utc>,
         ctx_duration: Duration,
     ) -> Result<(), TimeError> {
         let activity = NewCodingActivity {
@@ -24,6 +30,7 @@ impl super::DatabaseWrapper {
             language: heartbeat.language,
             editor_name: heartbeat.editor_name,
             hostname: heartbeat.hostname,
+            source: ActivitySource::Editor,
         };
 
         let mut conn = self.db.get().await?;
@@ -38,28 +45,151 @@ impl super::DatabaseWrapper {
         Ok(())
     }
 
-    pub async fn get_all_activity(&self, user: i32) -> Result<Vec<CodingActivity>, TimeError> {
+    /// Stores finished coding sessions. Sessions overlapping activities that are already stored
+    /// are skipped, so heartbeats that are sent again are not counted twice. Returns the number
+    /// of stored and skipped sessions.
+    pub async fn add_activities(
+        &self,
+        updated_user_id: i32,
+        sessions: Vec<(HeartBeat, DateTime<Utc>, Duration)>,
+    ) -> Result<(usize, usize), TimeError> {
+        let activities = sessions
+            .into_iter()
+            .map(
+                |(heartbeat, ctx_start_time, ctx_duration)| NewCodingActivity {
+                    user_id: updated_user_id,
+                    start_time: ctx_start_time,
+                    duration: ctx_duration.num_seconds() as i32,
+                    project_name: heartbeat.project_name,
+                    language: heartbeat.language,
+                    editor_name: heartbeat.editor_name,
+                    hostname: heartbeat.hostname,
+                    source: ActivitySource::Editor,
+                },
+            )
+            .collect::<Vec<_>>();
+        let session_count = activities.len();
+
+        let end_time =
+            |start: DateTime<Utc>, seconds: i32| start + Duration::seconds(seconds.into());
+        let (Some(first), Some(last)) = (
+            activities.iter().map(|a| a.start_time).min(),
+            activities
+                .iter()
+                .map(|a| end_time(a.start_time, a.duration))
+                .max(),
+        ) else {
+            return Ok((0, 0));
+        };
+
+        let mut conn = self.db.get().await?;
+
+        conn.build_transaction()
+            .read_write()
+            .run(|mut conn| {
+                Box::pin(async move {
+                    use crate::schema::{coding_activities::dsl::*, user_identities};
+
+                    // NOTE: Locks the user so that concurrent requests can't both store the
+                    // same session
+                    user_identities::table
+                        .find(updated_user_id)
+                        .select(user_identities::id)
+                        .for_update()
+                        .get_result::<i32>(&mut conn)
+                        .await?;
+
+                    let stored = Self::overlapping_activities(updated_user_id, first, last)
+                        .select((start_time, duration))
+                        .load::<(DateTime<Utc>, i32)>(&mut conn)
+                        .await?;
+
+                    let activities = activities
+                        .into_iter()
+                        .filter(|a| {
+                            !stored.iter().any(|(start, seconds)| {
+                                *start < end_time(a.start_time, a.duration)
+                                    && end_time(*start, *seconds) > a.start_time
+                            })
+                        })
+                        .collect::<Vec<_>>();
+                    let skipped = session_count - activities.len();
+
+                    if activities.is_empty() {
+                        return Ok((0, skipped));
+                    }
+
+                    let stored = diesel::insert_into(coding_activities)
+                        .values(activities)
+                        .execute(&mut conn)
+                        .await?;
+
+                    Ok::<_, TimeError>((stored, skipped))
+                })
+            })
+            .await
+    }
+
+    pub async fn import_activities(
+        &self,
+        activities: Vec<NewCodingActivity>,
+    ) -> Result<usize, TimeError> {
         let mut conn = self.db.get().await?;
 
+        conn.build_transaction()
+            .read_write()
+            .run(|mut conn| {
+                Box::pin(async move {
+                    use crate::schema::coding_activities::dsl::*;
+
+                    let mut inserted = 0;
+                    let mut activities = activities.into_iter().peekable();
+                    // NOTE: Postgres limits the amount of bind parameters in a single query
+                    while activities.peek().is_some() {
+                        let chunk = activities.by_ref().take(1000).collect::<Vec<_>>();
+                        inserted += diesel::insert_into(coding_activities)
+                            .values(chunk)
+                            .execute(&mut conn)
+                            .await?;
+                    }
+
+                    Ok::<usize, TimeError>(inserted)
+                })
+            })
+            .await
+    }
+
+    /// Activities of the user that overlap the time range from `from` to `to`
+    fn overlapping_activities(
+        user: i32,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> crate::schema::coding_activities::BoxedQuery<'static, Pg> {
+        use diesel::sql_types::{Bool, Timestamptz};
+
         use crate::schema::coding_activities::dsl::*;
-        Ok(coding_activities
+
+        coding_activities
+            .into_boxed()
             .filter(user_id.eq(user))
-            .load::<CodingActivity>(&mut conn)
-            .await?)
+            .filter(start_time.lt(to))
+            .filter(
+                diesel::dsl::sql::<Bool>("start_time + duration * interval '1 second' > ")
+                    .bind::<Timestamptz, _>(from),
+            )
     }
 
-    pub async fn get_activity(
-        &self,
+    fn filter_activity(
         request: DataRequest,
         user: i32,
-    ) -> Result<Vec<CodingActivity>, TimeError> {
+    ) -> crate::schema::coding_activities::BoxedQuery<'static, Pg> {
         use crate::schema::coding_activities::dsl::*;
         let mut query = coding_activities.into_boxed().filter(user_id.eq(user));
         if let Some(from) = request.from {
-            query = query.filter(start_time.ge(from.naive_local()));
+            query = query.filter(start_time.ge(from));
         };
         if let Some(to) = request.to {
-            query = query.filter(start_time.le(to.naive_local()));
+            query = query.filter(start_time.le(to));
         };
         if let Some(editor) = request.editor_name {
             query = query.filter(editor_name.eq(editor));
@@ -77,14 +207,136 @@ impl super::DatabaseWrapper {
             query = query.filter(duration.ge(min_duration));
         };
 
+        let order = request.order.unwrap_or_default();
+        if let Some(cursor) = request.cursor {
+            query = match order {
+                SortOrder::Asc => query.filter(
+                    start_time
+                        .gt(cursor.start_time)
+                        .or(start_time.eq(cursor.start_time).and(id.gt(cursor.id))),
+                ),
+                SortOrder::Desc => query.filter(
+                    start_time
+                        .lt(cursor.start_time)
+                        .or(start_time.eq(cursor.start_time).and(id.lt(cursor.id))),
+                ),
+            };
+        };
+        query = match order {
+            SortOrder::Asc => query.order_by((start_time.asc(), id.asc())),
+            SortOrder::Desc => query.order_by((start_time.desc(), id.desc())),
+        };
+        if let Some(limit) = request.limit {
+            query = query.limit(limit);
+        };
+        query
+    }
+
+    pub async fn get_activity(
+        &self,
+        request: DataRequest,
+        user: i32,
+    ) -> Result<Vec<CodingActivity>, TimeError> {
+        let query = Self::filter_activity(request, user);
+
         let mut conn = self.db.get().await?;
         Ok(query.load::<CodingActivity>(&mut conn).await?)
     }
 
+    /// Sums the coding time of the user, grouped by the requested fields and time buckets.
+    /// The buckets start at midnight in the given timezone.
+    pub async fn get_activity_aggregate(
+        &self,
+        user: i32,
+        request: &AggregateRequest,
+        timezone: Tz,
+    ) -> Result<Vec<ActivityAggregate>, TimeError> {
+        use diesel::sql_types::{Integer, Nullable, Text, Timestamptz};
+
+        // NOTE: Only the fixed column names and units below are formatted into the query,
+        // everything the user sends is bound as a parameter
+        let bucket = request.bucket.map_or_else(
+            || String::from("NULL::timestamptz"),
+            |bucket| format!("date_trunc('{}', start_time, $4)", bucket.unit()),
+        );
+        let column = |field: AggregateField| {
+            if !request.group_by.contains(&field) {
+                return "NULL::text";
+            }
+            match field {
+                AggregateField::Project => "project_name",
+                AggregateField::Language => "language",
+                AggregateField::Editor => "editor_name",
+                AggregateField::Hostname => "hostname",
+            }
+        };
+
+        let query = format!(
+            "SELECT {bucket} AS bucket, {} AS project_name, {} AS language, \
+             {} AS editor_name, {} AS hostname, SUM(duration)::BIGINT AS total \
+             FROM coding_activities \
+             WHERE user_id = $1 \
+             AND start_time >= COALESCE($2, '-infinity') \
+             AND start_time <= COALESCE($3, 'infinity') \
+             GROUP BY 1, 2, 3, 4, 5 \
+             ORDER BY 1, 6 DESC",
+            column(AggregateField::Project),
+            column(AggregateField::Language),
+            column(AggregateField::Editor),
+            column(AggregateField::Hostname),
+        );
+
+        let mut conn = self.db.get().await?;
+
+        Ok(diesel::sql_query(query)
+            .bind::<Integer, _>(user)
+            .bind::<Nullable<Timestamptz>, _>(request.from)
+            .bind::<Nullable<Timestamptz>, _>(request.to)
+            .bind::<Text, _>(timezone.name())
+            .load::<ActivityAggregate>(&mut conn)
+            .await?)
+    }
+
+    /// Streams the matching activities of the user without loading all of them into memory.
+    pub fn stream_activity(
+        &self,
+        request: DataRequest,
+        user: i32,
+    ) -> mpsc::Receiver<Result<CodingActivity, TimeError>> {
+        let query = Self::filter_activity(request, user);
+
+        let db = Arc::clone(&self.db);
+        let (mut sender, receiver) = mpsc::channel(64);
+
+        // NOTE: The stream borrows the connection, so it has to be driven in its own task
+        actix_web::rt::spawn(async move {
+            let result = async {
+                let mut conn = db.get().await?;
+                let mut activities = query.load_stream::<CodingActivity>(&mut conn).await?;
+
+                while let Some(activity) = activities.try_next().await? {
+                    if sender.send(Ok(activity)).await.is_err() {
+                        // The receiver was dropped
+                        break;
+                    }
+                }
+
+                Ok::<(), TimeError>(())
+            }
+            .await;
+
+            if let Err(e) = result {
+                sender.send(Err(e)).await.ok();
+            }
+        });
+
+        receiver
+    }
+
     pub async fn get_user_coding_time_since(
         &self,
         uid: i32,
-        since: chrono::NaiveDateTime,
+        since: DateTime<Utc>,
     ) -> Result<i32, TimeError> {
         let mut conn = self.db.get().await?;
 
@@ -98,27 +350,55 @@ impl super::DatabaseWrapper {
             .unwrap_or(0) as i32)
     }
 
-    pub async fn get_coding_time_steps(&self, uid: i32) -> CodingTimeSteps {
+    /// Returns the days on which the user has coded at least `min_duration` seconds
+    /// in ascending order. The days are split at midnight in the given timezone.
+    pub async fn get_coding_days(
+        &self,
+        user: i32,
+        min_duration: i64,
+        timezone: Tz,
+    ) -> Result<Vec<NaiveDate>, TimeError> {
+        use diesel::sql_types::{BigInt, Date, Integer, Text};
+
+        #[derive(QueryableByName)]
+        struct CodingDay {
+            #[diesel(sql_type = Date)]
+            day: NaiveDate,
+        }
+
+        let mut conn = self.db.get().await?;
+
+        Ok(diesel::sql_query(
+            "SELECT (start_time AT TIME ZONE $2)::date AS day \
+             FROM coding_activities \
+             WHERE user_id = $1 \
+             GROUP BY 1 \
+             HAVING SUM(duration) >= $3 \
+             ORDER BY 1",
+        )
+        .bind::<Integer, _>(user)
+        .bind::<Text, _>(timezone.name())
+        .bind::<BigInt, _>(min_duration)
+        .load::<CodingDay>(&mut conn)
+        .await?
+        .into_iter()
+        .map(|d| d.day)
+        .collect())
+    }
+
+    /// Coding time of the user, the past month and week start at midnight in the given timezone
+    pub async fn get_coding_time_steps(&self, uid: i32, timezone: Tz) -> CodingTimeSteps {
         CodingTimeSteps {
             all_time: self
-                .get_user_coding_time_since(
-                    uid,
-                    chrono::NaiveDateTime::from_timestamp_opt(0, 0).unwrap(),
-                )
+                .get_user_coding_time_since(uid, Utc.timestamp_opt(0, 0).unwrap())
                 .await
                 .unwrap_or(0),
             past_month: self
-                .get_user_coding_time_since(
-                    uid,
-                    chrono::Local::now().naive_local() - chrono::Duration::days(30),
-                )
+                .get_user_coding_time_since(uid, days_ago_at_midnight(timezone, 30))
                 .await
                 .unwrap_or(0),
             past_week: self
-                .get_user_coding_time_since(
-                    uid,
-                    chrono::Local::now().naive_local() - chrono::Duration::days(7),
-                )
+                .get_user_coding_time_since(uid, days_ago_at_midnight(timezone, 7))
                 .await
                 .unwrap_or(0),
         }
@@ -141,6 +421,175 @@ impl super::DatabaseWrapper {
             .await?)
     }
 
+    /// Deletes the activities of the user matching the request in a single transaction.
+    /// Returns the number of matching activities, on a dry run nothing is deleted.
+    pub async fn delete_activities(
+        &self,
+        user: i32,
+        request: DataRequest,
+        dry_run: bool,
+    ) -> Result<usize, TimeError> {
+        use crate::schema::coding_activities::dsl::*;
+
+        let matching = Self::filter_activity(request, user).select(id);
+
+        let mut conn = self.db.get().await?;
+
+        conn.build_transaction()
+            .read_write()
+            .run(|mut conn| {
+                Box::pin(async move {
+                    let targets = coding_activities.filter(id.eq_any(matching));
+
+                    if dry_run {
+                        return Ok(targets.count().get_result::<i64>(&mut conn).await? as usize);
+                    }
+
+                    Ok::<usize, TimeError>(diesel::delete(targets).execute(&mut conn).await?)
+                })
+            })
+            .await
+    }
+
+    /// Changes the fields of the activities of the user matching the request in a single
+    /// transaction. Returns the number of matching activities, on a dry run nothing is changed.
+    pub async fn update_activities(
+        &self,
+        user: i32,
+        request: DataRequest,
+        changes: ActivityChanges,
+        dry_run: bool,
+    ) -> Result<usize, TimeError> {
+        use crate::schema::coding_activities::dsl::*;
+
+        let matching = Self::filter_activity(request, user).select(id);
+
+        let mut conn = self.db.get().await?;
+
+        conn.build_transaction()
+            .read_write()
+            .run(|mut conn| {
+                Box::pin(async move {
+                    let targets = coding_activities.filter(id.eq_any(matching));
+
+                    if dry_run {
+                        return Ok(targets.count().get_result::<i64>(&mut conn).await? as usize);
+                    }
+
+                    Ok::<usize, TimeError>(
+                        diesel::update(targets)
+                            .set(changes)
+                            .execute(&mut conn)
+                            .await?,
+                    )
+                })
+            })
+            .await
+    }
+
+    /// Adds a manual activity for the user. Manual activities cannot overlap other activities.
+    pub async fn add_manual_activity(
+        &self,
+        user: i32,
+        activity: ManualActivity,
+    ) -> Result<CodingActivity, TimeError> {
+        let end = activity.start_time + Duration::seconds(activity.duration.into());
+        let activity = NewCodingActivity {
+            user_id: user,
+            start_time: activity.start_time,
+            duration: activity.duration,
+            project_name: activity.project_name,
+            language: activity.language,
+            editor_name: None,
+            hostname: None,
+            source: ActivitySource::Manual,
+        };
+
+        let mut conn = self.db.get().await?;
+
+        conn.build_transaction()
+            .read_write()
+            .run(|mut conn| {
+                Box::pin(async move {
+                    use crate::schema::coding_activities::dsl::*;
+
+                    let overlapping = diesel::select(diesel::dsl::exists(
+                        Self::overlapping_activities(user, activity.start_time, end),
+                    ))
+                    .get_result::<bool>(&mut conn)
+                    .await?;
+
+                    if overlapping {
+                        return Err(TimeError::OverlappingActivity);
+                    }
+
+                    Ok(diesel::insert_into(coding_activities)
+                        .values(activity)
+                        .get_result(&mut conn)
+                        .await?)
+                })
+            })
+            .await
+    }
+
+    /// Replaces the fields of a manual activity of the user, activities recorded by editors
+    /// cannot be edited. The activity cannot be moved to overlap other activities.
+    pub async fn update_manual_activity(
+        &self,
+        user: i32,
+        activity: i32,
+        changes: ManualActivity,
+    ) -> Result<Option<CodingActivity>, TimeError> {
+        let end = changes.start_time + Duration::seconds(changes.duration.into());
+
+        let mut conn = self.db.get().await?;
+
+        conn.build_transaction()
+            .read_write()
+            .run(|mut conn| {
+                Box::pin(async move {
+                    use crate::schema::coding_activities::dsl::*;
+
+                    let overlapping = diesel::select(diesel::dsl::exists(
+                        Self::overlapping_activities(user, changes.start_time, end)
+                            .filter(id.ne(activity)),
+                    ))
+                    .get_result::<bool>(&mut conn)
+                    .await?;
+
+                    if overlapping {
+                        return Err(TimeError::OverlappingActivity);
+                    }
+
+                    Ok(diesel::update(coding_activities.find(activity))
+                        .filter(user_id.eq(user))
+                        .filter(source.eq(ActivitySource::Manual.as_str()))
+                        .set(changes)
+                        .get_result(&mut conn)
+                        .await
+                        .optional()?)
+                })
+            })
+            .await
+    }
+
+    pub async fn delete_manual_activity(
+        &self,
+        user: i32,
+        activity: i32,
+    ) -> Result<bool, TimeError> {
+        let mut conn = self.db.get().await?;
+
+        use crate::schema::coding_activities::dsl::*;
+
+        Ok(diesel::delete(coding_activities.find(activity))
+            .filter(user_id.eq(user))
+            .filter(source.eq(ActivitySource::Manual.as_str()))
+            .execute(&mut conn)
+            .await?
+            != 0)
+    }
+
     pub async fn delete_activity(&self, userid: i32, activity: i32) -> Result<bool, TimeError> {
         let mut conn = self.db.get().await?;
 
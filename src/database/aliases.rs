@@ -0,0 +1,125 @@
+use diesel::{insert_into, prelude::*};
+use diesel_async::RunQueryDsl;
+
+use crate::{
+    error::TimeError,
+    models::{NewProjectAlias, ProjectAlias, ProjectAliasRules},
+};
+
+impl super::DatabaseWrapper {
+    pub async fn get_project_aliases(&self, user: i32) -> Result<Vec<ProjectAlias>, TimeError> {
+        let mut conn = self.db.get().await?;
+
+        use crate::schema::project_aliases::dsl::*;
+
+        Ok(project_aliases
+            .filter(user_id.eq(user))
+            .order(id.asc())
+            .select((id, pattern, match_kind, target, creation_time))
+            .load::<ProjectAlias>(&mut conn)
+            .await?)
+    }
+
+    pub async fn get_project_alias_rules(&self, user: i32) -> Result<ProjectAliasRules, TimeError> {
+        Ok(ProjectAliasRules::new(
+            &self.get_project_aliases(user).await?,
+        ))
+    }
+
+    /// Adds a project alias for the user if they have less than `max_aliases` aliases
+    pub async fn add_project_alias(
+        &self,
+        alias: NewProjectAlias,
+        max_aliases: i64,
+    ) -> Result<ProjectAlias, TimeError> {
+        let mut conn = self.db.get().await?;
+
+        conn.build_transaction()
+            .read_write()
+            .run(|mut conn| {
+                Box::pin(async move {
+                    use crate::schema::{project_aliases::dsl::*, user_identities};
+
+                    // NOTE: Locks the user so that concurrent requests can't exceed the limit
+                    user_identities::table
+                        .find(alias.user_id)
+                        .select(user_identities::id)
+                        .for_update()
+                        .get_result::<i32>(&mut conn)
+                        .await?;
+
+                    let count = project_aliases
+                        .filter(user_id.eq(alias.user_id))
+                        .count()
+                        .get_result::<i64>(&mut conn)
+                        .await?;
+
+                    if count >= max_aliases {
+                        return Err(TimeError::InvalidLength(format!(
+                            "There can be at most {max_aliases} project aliases"
+                        )));
+                    }
+
+                    Ok(insert_into(project_aliases)
+                        .values(alias)
+                        .returning((id, pattern, match_kind, target, creation_time))
+                        .get_result(&mut conn)
+                        .await?)
+                })
+            })
+            .await
+    }
+
+    pub async fn delete_project_alias(&self, user: i32, alias: i32) -> Result<bool, TimeError> {
+        let mut conn = self.db.get().await?;
+
+        use crate::schema::project_aliases::dsl::*;
+
+        Ok(diesel::delete(project_aliases.find(alias))
+            .filter(user_id.eq(user))
+            .execute(&mut conn)
+            .await?
+            != 0)
+    }
+
+    /// Renames the projects of the existing activities of the user as if they had been sent
+    /// with the given rules. Returns the number of affected activities.
+    pub async fn backfill_project_aliases(
+        &self,
+        user: i32,
+        rules: ProjectAliasRules,
+    ) -> Result<usize, TimeError> {
+        let mut conn = self.db.get().await?;
+
+        use crate::schema::coding_activities::dsl::*;
+
+        conn.build_transaction()
+            .read_write()
+            .run(|mut conn| {
+                Box::pin(async move {
+                    let projects = coding_activities
+                        .filter(user_id.eq(user))
+                        .select(project_name)
+                        .distinct()
+                        .load::<Option<String>>(&mut conn)
+                        .await?;
+
+                    let mut affected = 0;
+                    for project in projects.into_iter().flatten() {
+                        let target = rules.apply(project.clone());
+                        if target != project {
+                            affected += diesel::update(coding_activities)
+                                .filter(user_id.eq(user))
+                                .filter(project_name.eq(project))
+                                .set(project_name.eq(target))
+                                .execute(&mut conn)
+                                .await?;
+                        }
+                    }
+
+                    Ok::<usize, TimeError>(affected)
+                })
+            })
+            .await
+    }
+}
@@ -2,6 +2,7 @@ use argon2::{
     password_hash::{rand_core::OsRng, PasswordHasher, SaltString},
     Argon2,
 };
+use chrono_tz::Tz;
 use diesel::prelude::*;
 use diesel_async::RunQueryDsl;
 
@@ -305,4 +306,15 @@ impl super::DatabaseWrapper {
             .await?;
         Ok(())
     }
+
+    pub async fn change_timezone(&self, userid: i32, tz: Tz) -> Result<(), TimeError> {
+        let mut conn = self.db.get().await?;
+
+        use crate::schema::user_identities::dsl::*;
+        diesel::update(user_identities.find(userid))
+            .set(timezone.eq(tz.name()))
+            .execute(&mut conn)
+            .await?;
+        Ok(())
+    }
 }
@@ -1,15 +1,28 @@
+use chrono_tz::Tz;
 use diesel::{insert_into, prelude::*};
 use diesel_async::RunQueryDsl;
 
-use crate::{error::TimeError, models::*};
+use crate::{error::TimeError, models::*, utils::days_ago_at_midnight};
 
 impl super::DatabaseWrapper {
-    pub async fn add_friend(&self, user: i32, friend: String) -> Result<UserIdentity, TimeError> {
+    /// Sends a friend request to the user with the given friend code or username. If they
+    /// have already sent a request to the user, it is accepted instead. Returns the other
+    /// user and whether they are now friends.
+    pub async fn send_friend_request(
+        &self,
+        user: i32,
+        friend: String,
+    ) -> Result<(UserIdentity, bool), TimeError> {
+        use crate::schema::{friend_relations::dsl::*, user_identities::dsl as users};
+
         let mut conn = self.db.get().await?;
-        use crate::schema::user_identities::dsl::*;
 
-        let Some(friend) = user_identities
-            .filter(friend_code.eq(friend))
+        let Some(friend) = users::user_identities
+            .filter(
+                users::friend_code
+                    .eq(&friend)
+                    .or(users::username.eq(&friend)),
+            )
             .first::<UserIdentity>(&mut conn)
             .await
             .optional()?
@@ -21,33 +34,185 @@ impl super::DatabaseWrapper {
             return Err(TimeError::CurrentUser);
         }
 
+        // NOTE: Blocked users should not find out that they have been blocked
+        if self.is_blocked_either_way(user, friend.id).await? {
+            return Err(TimeError::UserNotFound);
+        }
+
         let (lesser, greater) = if user < friend.id {
             (user, friend.id)
         } else {
             (friend.id, user)
         };
 
-        insert_into(crate::schema::friend_relations::table)
-            .values(crate::models::NewFriendRelation {
-                lesser_id: lesser,
-                greater_id: greater,
+        let accepted_now = conn
+            .build_transaction()
+            .read_write()
+            .run(|mut conn| {
+                Box::pin(async move {
+                    // NOTE: If the other user sends a request at the same time, the insert
+                    // does nothing and their request is accepted on the second round
+                    for _ in 0..2 {
+                        let relation = friend_relations
+                            .filter(lesser_id.eq(lesser).and(greater_id.eq(greater)))
+                            .first::<FriendRelation>(&mut conn)
+                            .await
+                            .optional()?;
+
+                        match relation {
+                            None => {
+                                let inserted = insert_into(friend_relations)
+                                    .values(NewFriendRelation {
+                                        lesser_id: lesser,
+                                        greater_id: greater,
+                                        requester_id: Some(user),
+                                        accepted: false,
+                                    })
+                                    .on_conflict((lesser_id, greater_id))
+                                    .do_nothing()
+                                    .execute(&mut conn)
+                                    .await?;
+
+                                if inserted != 0 {
+                                    return Ok(false);
+                                }
+                            }
+                            Some(relation) if relation.accepted => {
+                                return Err(TimeError::AlreadyFriends)
+                            }
+                            Some(relation) if relation.requester_id == Some(user) => {
+                                return Err(TimeError::FriendRequestExists)
+                            }
+                            Some(relation) => {
+                                diesel::update(friend_relations.find(relation.id))
+                                    .set(accepted.eq(true))
+                                    .execute(&mut conn)
+                                    .await?;
+                                return Ok::<bool, TimeError>(true);
+                            }
+                        }
+                    }
+
+                    Err(TimeError::UnknownError)
+                })
             })
-            .execute(&mut conn)
             .await?;
 
-        Ok(friend)
+        Ok((friend, accepted_now))
+    }
+
+    /// Returns the pending friend requests sent to and by the user, the oldest first
+    pub async fn get_friend_requests(&self, user: i32) -> Result<FriendRequests, TimeError> {
+        use crate::schema::{friend_relations::dsl::*, user_identities::dsl as users};
+
+        let mut conn = self.db.get().await?;
+
+        let incoming = friend_relations
+            .inner_join(users::user_identities.on(users::id.nullable().eq(requester_id)))
+            .filter(lesser_id.eq(user).or(greater_id.eq(user)))
+            .filter(requester_id.ne(user))
+            .filter(accepted.eq(false))
+            .order_by(creation_time)
+            .select((users::username, creation_time))
+            .load::<FriendRequest>(&mut conn)
+            .await?;
+
+        let outgoing = friend_relations
+            .inner_join(
+                users::user_identities.on(users::id.eq(lesser_id).or(users::id.eq(greater_id))),
+            )
+            .filter(requester_id.eq(user))
+            .filter(users::id.ne(user))
+            .filter(accepted.eq(false))
+            .order_by(creation_time)
+            .select((users::username, creation_time))
+            .load::<FriendRequest>(&mut conn)
+            .await?;
+
+        Ok(FriendRequests { incoming, outgoing })
+    }
+
+    /// Accepts or declines the pending friend request sent by `requester` to the user.
+    /// Returns whether there was a request.
+    pub async fn respond_to_friend_request(
+        &self,
+        user: i32,
+        requester: i32,
+        response: FriendRequestResponse,
+    ) -> Result<bool, TimeError> {
+        use crate::schema::friend_relations::dsl::*;
+
+        let (lesser, greater) = if user < requester {
+            (user, requester)
+        } else {
+            (requester, user)
+        };
+
+        let mut conn = self.db.get().await?;
+
+        conn.build_transaction()
+            .read_write()
+            .run(|mut conn| {
+                Box::pin(async move {
+                    let request = friend_relations
+                        .filter(lesser_id.eq(lesser).and(greater_id.eq(greater)))
+                        .filter(requester_id.eq(requester))
+                        .filter(accepted.eq(false));
+
+                    let found = if response == FriendRequestResponse::Accept {
+                        diesel::update(request)
+                            .set(accepted.eq(true))
+                            .execute(&mut conn)
+                            .await?
+                    } else {
+                        diesel::delete(request).execute(&mut conn).await?
+                    };
+
+                    if found != 0 && response == FriendRequestResponse::Block {
+                        insert_into(crate::schema::user_blocks::table)
+                            .values(NewUserBlock {
+                                blocker_id: user,
+                                blocked_id: requester,
+                            })
+                            .on_conflict_do_nothing()
+                            .execute(&mut conn)
+                            .await?;
+                    }
+
+                    Ok::<bool, TimeError>(found != 0)
+                })
+            })
+            .await
+    }
+
+    pub async fn is_blocked_either_way(&self, user: i32, other: i32) -> Result<bool, TimeError> {
+        use crate::schema::user_blocks::dsl::*;
+
+        let mut conn = self.db.get().await?;
+
+        Ok(diesel::select(diesel::dsl::exists(
+            user_blocks.filter(
+                blocker_id
+                    .eq(user)
+                    .and(blocked_id.eq(other))
+                    .or(blocker_id.eq(other).and(blocked_id.eq(user))),
+            ),
+        ))
+        .get_result::<bool>(&mut conn)
+        .await?)
     }
 
-    #[allow(dead_code)]
     pub async fn get_friends(&self, user: i32) -> Result<Vec<UserIdentity>, TimeError> {
         use crate::schema::{
-            friend_relations::dsl::{friend_relations, greater_id, lesser_id},
+            friend_relations::dsl::{accepted, friend_relations, greater_id, lesser_id},
             user_identities::dsl::*,
         };
 
         let mut conn = self.db.get().await?;
 
         let friends = friend_relations
+            .filter(lesser_id.eq(user).or(greater_id.eq(user)))
+            .filter(accepted.eq(true))
             .inner_join(user_identities.on(id.eq(lesser_id).or(id.eq(greater_id))))
             .select(user_identities::all_columns())
             .distinct()
@@ -58,9 +223,15 @@ impl super::DatabaseWrapper {
         Ok(friends)
     }
 
-    pub async fn get_friends_with_time(&self, user: i32) -> Result<Vec<FriendWithTime>, TimeError> {
+    /// Returns the friends of the user with their coding time, the past month and week
+    /// start at midnight in the given timezone
+    pub async fn get_friends_with_time(
+        &self,
+        user: i32,
+        tz: Tz,
+    ) -> Result<Vec<FriendWithTime>, TimeError> {
         use crate::schema::{
-            friend_relations::dsl::{friend_relations, greater_id, lesser_id},
+            friend_relations::dsl::{accepted, friend_relations, greater_id, lesser_id},
             user_identities::dsl::*,
         };
 
@@ -68,6 +239,7 @@ impl super::DatabaseWrapper {
 
         let friends = friend_relations
             .filter(lesser_id.eq(user).or(greater_id.eq(user)))
+            .filter(accepted.eq(true))
             .inner_join(user_identities.on(id.eq(lesser_id).or(id.eq(greater_id))))
             .distinct()
             .filter(id.ne(user))
@@ -80,6 +252,9 @@ impl super::DatabaseWrapper {
         // in the database. This can be done in a single SQL-query
         // but due to limitations with diesel we would have to do it
         // in a separate function called for example: coding_time_steps_for_users(ids: Vec<i32>)
+        let month_ago = days_ago_at_midnight(tz, 30);
+        let week_ago = days_ago_at_midnight(tz, 7);
+
         let friends_with_time = CodingActivity::belonging_to(&friends)
             .load::<CodingActivity>(&mut conn)
             .await?
@@ -93,9 +268,7 @@ impl super::DatabaseWrapper {
                     past_month: d
                         .iter()
                         .map(|a| {
-                            if a.start_time
-                                >= chrono::Local::now().naive_local() - chrono::Duration::days(30)
-                            {
+                            if a.start_time >= month_ago {
                                 a.duration
                             } else {
                                 0
@@ -105,9 +278,7 @@ impl super::DatabaseWrapper {
                     past_week: d
                         .iter()
                         .map(|a| {
-                            if a.start_time
-                                >= chrono::Local::now().naive_local() - chrono::Duration::days(7)
-                            {
+                            if a.start_time >= week_ago {
                                 a.duration
                             } else {
                                 0
@@ -133,6 +304,7 @@ impl super::DatabaseWrapper {
 
         Ok(friend_relations
             .filter(lesser_id.eq(lesser).and(greater_id.eq(greater)))
+            .filter(accepted.eq(true))
             .first::<FriendRelation>(&mut conn)
             .await
             .optional()?
@@ -156,6 +328,144 @@ impl super::DatabaseWrapper {
             != 0)
     }
 
+    /// Blocks the user, removing any friendship or pending friend request between the users
+    pub async fn block_user(&self, user: i32, blocked: i32) -> Result<(), TimeError> {
+        use crate::schema::friend_relations::dsl::*;
+
+        let (lesser, greater) = if user < blocked {
+            (user, blocked)
+        } else {
+            (blocked, user)
+        };
+
+        let mut conn = self.db.get().await?;
+
+        conn.build_transaction()
+            .read_write()
+            .run(|mut conn| {
+                Box::pin(async move {
+                    diesel::delete(friend_relations)
+                        .filter(lesser_id.eq(lesser).and(greater_id.eq(greater)))
+                        .execute(&mut conn)
+                        .await?;
+
+                    insert_into(crate::schema::user_blocks::table)
+                        .values(NewUserBlock {
+                            blocker_id: user,
+                            blocked_id: blocked,
+                        })
+                        .on_conflict_do_nothing()
+                        .execute(&mut conn)
+                        .await?;
+
+                    Ok::<(), TimeError>(())
+                })
+            })
+            .await
+    }
+
+    pub async fn unblock_user(&self, user: i32, blocked: i32) -> Result<bool, TimeError> {
+        use crate::schema::user_blocks::dsl::*;
+
+        let mut conn = self.db.get().await?;
+
+        Ok(diesel::delete(user_blocks)
+            .filter(blocker_id.eq(user).and(blocked_id.eq(blocked)))
+            .execute(&mut conn)
+            .await?
+            != 0)
+    }
+
+    pub async fn get_blocked_users(&self, user: i32) -> Result<Vec<BlockedUser>, TimeError> {
+        use crate::schema::{user_blocks::dsl::*, user_identities::dsl as users};
+
+        let mut conn = self.db.get().await?;
+
+        Ok(user_blocks
+            .inner_join(users::user_identities.on(users::id.eq(blocked_id)))
+            .filter(blocker_id.eq(user))
+            .order_by(creation_time)
+            .select((users::username, creation_time))
+            .load::<BlockedUser>(&mut conn)
+            .await?)
+    }
+
+    /// Returns what the user shares with the friend
+    pub async fn get_friend_visibility(
+        &self,
+        user: i32,
+        friend: i32,
+    ) -> Result<FriendVisibility, TimeError> {
+        use crate::schema::friend_visibility::dsl::*;
+
+        let mut conn = self.db.get().await?;
+
+        Ok(friend_visibility
+            .find((user, friend))
+            .first::<FriendVisibility>(&mut conn)
+            .await
+            .optional()?
+            .unwrap_or(FriendVisibility {
+                user_id: user,
+                friend_id: friend,
+                ..Default::default()
+            }))
+    }
+
+    /// Returns what the user shares with each of their friends by the username of the friend,
+    /// friends with whom everything is shared may be missing
+    pub async fn get_friend_visibilities(
+        &self,
+        user: i32,
+    ) -> Result<Vec<(String, FriendVisibility)>, TimeError> {
+        use crate::schema::{friend_visibility::dsl::*, user_identities::dsl as users};
+
+        let mut conn = self.db.get().await?;
+
+        Ok(friend_visibility
+            .inner_join(users::user_identities.on(users::id.eq(friend_id)))
+            .filter(user_id.eq(user))
+            .order_by(users::username)
+            .select((users::username, friend_visibility::all_columns()))
+            .load::<(String, FriendVisibility)>(&mut conn)
+            .await?)
+    }
+
+    /// Returns what the friends of the user share with them, friends who share everything
+    /// may be missing
+    pub async fn get_visibilities_for_friend(
+        &self,
+        friend: i32,
+    ) -> Result<Vec<FriendVisibility>, TimeError> {
+        use crate::schema::friend_visibility::dsl::*;
+
+        let mut conn = self.db.get().await?;
+
+        Ok(friend_visibility
+            .filter(friend_id.eq(friend))
+            .load::<FriendVisibility>(&mut conn)
+            .await?)
+    }
+
+    pub async fn set_friend_visibility(
+        &self,
+        visibility: FriendVisibility,
+    ) -> Result<(), TimeError> {
+        use crate::schema::friend_visibility::dsl::*;
+
+        let mut conn = self.db.get().await?;
+
+        insert_into(friend_visibility)
+            .values(&visibility)
+            .on_conflict((user_id, friend_id))
+            .do_update()
+            .set(&visibility)
+            .execute(&mut conn)
+            .await?;
+
+        Ok(())
+    }
+
     pub async fn regenerate_friend_code(&self, userid: i32) -> Result<String, TimeError> {
         use crate::schema::user_identities::dsl::*;
         let code = crate::utils::generate_friend_code();
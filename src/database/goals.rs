@@ -0,0 +1,38 @@
+use diesel::{insert_into, prelude::*};
+use diesel_async::RunQueryDsl;
+
+use crate::{error::TimeError, models::CodingGoals};
+
+impl super::DatabaseWrapper {
+    pub async fn get_coding_goals(&self, user: i32) -> Result<CodingGoals, TimeError> {
+        let mut conn = self.db.get().await?;
+
+        use crate::schema::coding_goals::dsl::*;
+
+        Ok(coding_goals
+            .find(user)
+            .first::<CodingGoals>(&mut conn)
+            .await
+            .optional()?
+            .unwrap_or(CodingGoals {
+                user_id: user,
+                ..Default::default()
+            }))
+    }
+
+    pub async fn set_coding_goals(&self, goals: CodingGoals) -> Result<(), TimeError> {
+        let mut conn = self.db.get().await?;
+
+        use crate::schema::coding_goals::dsl::*;
+
+        insert_into(coding_goals)
+            .values(&goals)
+            .on_conflict(user_id)
+            .do_update()
+            .set(&goals)
+            .execute(&mut conn)
+            .await?;
+
+        Ok(())
+    }
+}
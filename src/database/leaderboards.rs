@@ -1,32 +1,81 @@
 use std::collections::HashMap;
 
-use chrono::prelude::*;
+use chrono_tz::Tz;
 use diesel::{insert_into, prelude::*};
 use diesel_async::RunQueryDsl;
-use futures_util::TryStreamExt;
 
 use crate::{
     api::users::ListLeaderboard,
     error::TimeError,
     models::*,
     schema::{
-        coding_activities,
+        leaderboard_bans, leaderboard_join_requests,
         leaderboard_members::{self, user_id},
         user_identities,
     },
 };
 
+/// Ranks the members of the leaderboards `$1` by the activities that started between the
+/// times `$2` and `$3` given for each leaderboard, counting only the activities matching
+/// the filters of the leaderboard. Active days are calculated in the timezone `$4`.
+/// Private projects of the members never match the project filter.
+const RANKING_QUERY: &str = "\
+    WITH boards AS ( \
+    SELECT * FROM unnest($1::INTEGER[], $2::TIMESTAMPTZ[], $3::TIMESTAMPTZ[]) \
+    AS b(id, from_time, to_time)), \
+    scores AS ( \
+    SELECT l.id AS leaderboard_id, u.id, u.username, m.admin, \
+    COALESCE(SUM(a.duration), 0)::INTEGER AS time_coded, \
+    (CASE l.metric \
+    WHEN 'active_days' THEN COUNT(DISTINCT (a.start_time AT TIME ZONE $4)::date) \
+    WHEN 'projects' THEN COUNT(DISTINCT a.project_name) \
+    ELSE COALESCE(SUM(a.duration), 0) END)::BIGINT AS score \
+    FROM boards b \
+    INNER JOIN leaderboards l ON l.id = b.id \
+    INNER JOIN leaderboard_members m ON m.leaderboard_id = l.id \
+    INNER JOIN user_identities u ON u.id = m.user_id \
+    LEFT JOIN privacy_settings p ON p.user_id = m.user_id \
+    LEFT JOIN coding_activities a ON a.user_id = m.user_id \
+    AND a.start_time >= b.from_time \
+    AND (b.to_time IS NULL OR a.start_time < b.to_time) \
+    AND (l.languages IS NULL OR lower(a.language) = ANY(l.languages)) \
+    AND (l.project_pattern IS NULL OR (a.project_name LIKE l.project_pattern \
+    AND NOT COALESCE(lower(a.project_name) = ANY( \
+    SELECT lower(private) FROM unnest(p.private_projects) AS private), false))) \
+    AND (l.editor IS NULL OR a.editor_name = l.editor) \
+    AND (NOT l.exclude_manual OR a.source <> 'manual') \
+    GROUP BY l.id, u.id, u.username, m.admin), \
+    ranking AS ( \
+    SELECT *, \
+    ROW_NUMBER() OVER (PARTITION BY leaderboard_id \
+    ORDER BY score DESC, time_coded DESC, username)::INTEGER AS position, \
+    COUNT(*) OVER (PARTITION BY leaderboard_id)::INTEGER AS member_count \
+    FROM scores)";
+
 impl super::DatabaseWrapper {
     pub async fn create_leaderboard(
         &self,
         creator_id: i32,
         name: &str,
+        config: LeaderboardConfig,
     ) -> Result<String, TimeError> {
         let code = crate::utils::generate_token();
         let board = NewLeaderboard {
             name: name.to_string(),
-            creation_time: chrono::Local::now().naive_local(),
+            creation_time: chrono::Utc::now(),
             invite_code: code.clone(),
+            time_window: config.time_window,
+            window_days: config.window_days,
+            metric: config.metric,
+            languages: config.languages,
+            project_pattern: config.project_pattern,
+            editor: config.editor,
+            public: config.public,
+            description: config.description,
+            avatar_url: config.avatar_url,
+            owner_id: Some(creator_id),
+            approval_required: config.approval_required,
+            exclude_manual: config.exclude_manual,
         };
 
         let mut conn = self.db.get().await?;
@@ -38,7 +87,7 @@ impl super::DatabaseWrapper {
                     use crate::schema::leaderboards::dsl::*;
 
                     let lid = insert_into(crate::schema::leaderboards::table)
-                        .values(&board)
+                        .values(board)
                         .returning(id)
                         .get_results(&mut conn)
                         .await?[0];
@@ -62,6 +111,100 @@ impl super::DatabaseWrapper {
         Ok(code)
     }
 
+    pub async fn update_leaderboard_config(
+        &self,
+        lid: i32,
+        config: LeaderboardConfig,
+    ) -> Result<(), TimeError> {
+        let mut conn = self.db.get().await?;
+
+        use crate::schema::leaderboards::dsl::*;
+        diesel::update(leaderboards.find(lid))
+            .set(config)
+            .execute(&mut conn)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn rename_leaderboard(&self, lid: i32, new_name: String) -> Result<(), TimeError> {
+        let mut conn = self.db.get().await?;
+
+        use crate::schema::leaderboards::dsl::*;
+        diesel::update(leaderboards.find(lid))
+            .set(name.eq(new_name))
+            .execute(&mut conn)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Makes the member the owner and an admin of the leaderboard
+    pub async fn transfer_leaderboard_ownership(
+        &self,
+        lid: i32,
+        uid: i32,
+    ) -> Result<(), TimeError> {
+        let mut conn = self.db.get().await?;
+
+        conn.build_transaction()
+            .read_write()
+            .run(|mut conn| {
+                Box::pin(async move {
+                    use crate::schema::{
+                        leaderboard_members::dsl::{admin, leaderboard_id, leaderboard_members},
+                        leaderboards::dsl::{leaderboards, owner_id},
+                    };
+
+                    diesel::update(leaderboard_members)
+                        .filter(leaderboard_id.eq(lid).and(user_id.eq(uid)))
+                        .set(admin.eq(true))
+                        .execute(&mut conn)
+                        .await?;
+
+                    diesel::update(leaderboards.find(lid))
+                        .set(owner_id.eq(uid))
+                        .execute(&mut conn)
+                        .await?;
+
+                    Ok::<(), TimeError>(())
+                })
+            })
+            .await
+    }
+
+    pub async fn create_invite_link(&self, link: NewInviteLink) -> Result<InviteLink, TimeError> {
+        let mut conn = self.db.get().await?;
+
+        use crate::schema::leaderboard_invites::dsl::*;
+        Ok(diesel::insert_into(leaderboard_invites)
+            .values(link)
+            .get_result::<InviteLink>(&mut conn)
+            .await?)
+    }
+
+    pub async fn get_invite_links(&self, lid: i32) -> Result<Vec<InviteLink>, TimeError> {
+        let mut conn = self.db.get().await?;
+
+        use crate::schema::leaderboard_invites::dsl::*;
+        Ok(leaderboard_invites
+            .filter(leaderboard_id.eq(lid))
+            .order_by(creation_time)
+            .load::<InviteLink>(&mut conn)
+            .await?)
+    }
+
+    pub async fn delete_invite_link(&self, lid: i32, link_name: String) -> Result<bool, TimeError> {
+        let mut conn = self.db.get().await?;
+
+        use crate::schema::leaderboard_invites::dsl::*;
+        Ok(diesel::delete(leaderboard_invites)
+            .filter(leaderboard_id.eq(lid).and(name.eq(link_name)))
+            .execute(&mut conn)
+            .await?
+            != 0)
+    }
+
     pub async fn regenerate_leaderboard_invite(&self, lid: i32) -> Result<String, TimeError> {
         let newinvite = crate::utils::generate_token();
 
@@ -102,53 +245,72 @@ impl super::DatabaseWrapper {
             .await?)
     }
 
-    pub async fn get_leaderboard(&self, lname: String) -> Result<PrivateLeaderboard, TimeError> {
+    pub async fn get_leaderboard_by_name(&self, lname: String) -> Result<Leaderboard, TimeError> {
         sql_function!(fn lower(x: diesel::sql_types::Text) -> Text);
+        use crate::schema::leaderboards::dsl::*;
+
         let mut conn = self.db.get().await?;
 
-        let board = {
-            use crate::schema::leaderboards::dsl::*;
+        Ok(leaderboards
+            .filter(lower(name).eq(lname.to_lowercase()))
+            .first::<Leaderboard>(&mut conn)
+            .await?)
+    }
 
-            leaderboards
-                .filter(lower(name).eq(lname.to_lowercase()))
-                .first::<Leaderboard>(&mut conn)
-                .await?
-        };
+    /// Ranks the members of the leaderboard by its metric over its time window, counting
+    /// only the activities matching its filters. The window and active days are calculated
+    /// in the given timezone.
+    pub async fn get_leaderboard_ranking(
+        &self,
+        board: &Leaderboard,
+        timezone: Tz,
+    ) -> Result<Vec<PrivateLeaderboardMember>, TimeError> {
+        let from = board.time_window.start(board.window_days, timezone);
+        self.get_leaderboard_ranking_between(board, from, None, timezone)
+            .await
+    }
 
-        use crate::schema::{
-            coding_activities::dsl::start_time,
-            user_identities::dsl::{id, user_identities},
-        };
+    /// Ranks the members of the leaderboard by the activities that started between `from`
+    /// and `to`. Private projects of the members never match the project filter.
+    pub async fn get_leaderboard_ranking_between(
+        &self,
+        board: &Leaderboard,
+        from: chrono::DateTime<chrono::Utc>,
+        to: Option<chrono::DateTime<chrono::Utc>>,
+        timezone: Tz,
+    ) -> Result<Vec<PrivateLeaderboardMember>, TimeError> {
+        use diesel::sql_types::{Array, Integer, Nullable, Text, Timestamptz};
 
-        let members = LeaderboardMember::belonging_to(&board)
-            .inner_join(user_identities.on(id.eq(user_id)))
-            .load::<(LeaderboardMember, UserIdentity)>(&mut conn)
-            .await?;
+        let mut conn = self.db.get().await?;
 
-        let aweekago = NaiveDateTime::new(
-            Local::now().date_naive() - chrono::Duration::weeks(1),
-            chrono::NaiveTime::from_num_seconds_from_midnight_opt(0, 0).unwrap(),
-        );
-
-        let members =
-            CodingActivity::belonging_to(&members.iter().map(|(_, u)| u).collect::<Vec<_>>())
-                .filter(start_time.ge(aweekago))
-                .load::<CodingActivity>(&mut conn)
-                .await?
-                .grouped_by(&members.iter().map(|(_, u)| u).collect::<Vec<_>>())
-                .iter()
-                .zip(members.iter())
-                .map(|(d, (m, u))| PrivateLeaderboardMember {
-                    id: u.id,
-                    username: u.username.clone(),
-                    admin: m.admin,
-                    time_coded: d.iter().map(|a| a.duration).sum(),
-                })
-                .collect::<Vec<_>>();
+        Ok(diesel::sql_query(format!(
+            "{RANKING_QUERY} \
+             SELECT id, username, admin, time_coded, score FROM ranking ORDER BY position"
+        ))
+        .bind::<Array<Integer>, _>(vec![board.id])
+        .bind::<Array<Timestamptz>, _>(vec![from])
+        .bind::<Array<Nullable<Timestamptz>>, _>(vec![to])
+        .bind::<Text, _>(timezone.name())
+        .load::<PrivateLeaderboardMember>(&mut conn)
+        .await?)
+    }
+
+    /// Returns the leaderboard with its members ranked in the given timezone
+    pub async fn get_leaderboard(
+        &self,
+        board: Leaderboard,
+        timezone: Tz,
+    ) -> Result<PrivateLeaderboard, TimeError> {
+        let members = self.get_leaderboard_ranking(&board, timezone).await?;
 
         Ok(PrivateLeaderboard {
+            owner: members
+                .iter()
+                .find(|member| Some(member.id) == board.owner_id)
+                .map(|member| member.username.clone()),
+            config: board.config(),
             name: board.name,
-            invite: board.invite_code,
+            invite: Some(board.invite_code),
             creation_time: board.creation_time,
             members,
         })
@@ -158,16 +320,47 @@ impl super::DatabaseWrapper {
         &self,
         uid: i32,
         invite: String,
-    ) -> Result<crate::api::users::MinimalLeaderboard, TimeError> {
-        use crate::schema::leaderboards::dsl::{invite_code, leaderboards};
+    ) -> Result<LeaderboardJoin, TimeError> {
+        use crate::schema::{
+            leaderboard_invites::dsl as links,
+            leaderboards::dsl::{invite_code, leaderboards},
+        };
 
         let mut conn = self.db.get().await?;
 
+        if let Some(board) = leaderboards
+            .filter(invite_code.eq(&invite))
+            .first::<Leaderboard>(&mut conn)
+            .await
+            .optional()?
+        {
+            return self.add_leaderboard_member(uid, board, None).await;
+        }
+
+        let link = links::leaderboard_invites
+            .filter(links::invite_code.eq(invite))
+            .first::<InviteLink>(&mut conn)
+            .await?;
+
         let board = leaderboards
-            .filter(invite_code.eq(invite))
+            .find(link.leaderboard_id)
             .first::<Leaderboard>(&mut conn)
             .await?;
 
+        self.add_leaderboard_member(uid, board, Some(link.id)).await
+    }
+
+    /// Adds the user to the leaderboard, or creates a join request if the leaderboard requires
+    /// approval. Counts a use of the invite link if one was used, for join requests the use is
+    /// counted once the request is approved. Banned users cannot join.
+    pub async fn add_leaderboard_member(
+        &self,
+        uid: i32,
+        board: Leaderboard,
+        link_id: Option<i32>,
+    ) -> Result<LeaderboardJoin, TimeError> {
+        let mut conn = self.db.get().await?;
+
         let user = NewLeaderboardMember {
             user_id: uid,
             leaderboard_id: board.id,
@@ -176,30 +369,280 @@ impl super::DatabaseWrapper {
 
         let name = board.name.clone();
 
-        let member_count = conn
-            .build_transaction()
+        conn.build_transaction()
             .read_write()
             .run(|mut conn| {
                 Box::pin(async move {
+                    let is_banned = diesel::select(diesel::dsl::exists(
+                        leaderboard_bans::table
+                            .filter(leaderboard_bans::leaderboard_id.eq(board.id))
+                            .filter(leaderboard_bans::user_id.eq(uid)),
+                    ))
+                    .get_result::<bool>(&mut conn)
+                    .await?;
+
+                    if is_banned {
+                        return Err(TimeError::BannedFromLeaderboard);
+                    }
+
+                    if board.approval_required {
+                        if let Some(link_id) = link_id {
+                            use crate::schema::leaderboard_invites::dsl::*;
+
+                            let valid = diesel::select(diesel::dsl::exists(
+                                leaderboard_invites
+                                    .find(link_id)
+                                    .filter(
+                                        max_uses.is_null().or(uses.lt(max_uses.assume_not_null())),
+                                    )
+                                    .filter(
+                                        expires_at.is_null().or(expires_at.gt(diesel::dsl::now)),
+                                    ),
+                            ))
+                            .get_result::<bool>(&mut conn)
+                            .await?;
+
+                            if !valid {
+                                return Err(TimeError::InviteExpired);
+                            }
+                        }
+
+                        let is_member = diesel::select(diesel::dsl::exists(
+                            LeaderboardMember::belonging_to(&board).filter(user_id.eq(uid)),
+                        ))
+                        .get_result::<bool>(&mut conn)
+                        .await?;
+
+                        if is_member {
+                            return Err(TimeError::AlreadyMember);
+                        }
+
+                        diesel::insert_into(leaderboard_join_requests::table)
+                            .values(NewJoinRequest {
+                                leaderboard_id: board.id,
+                                user_id: uid,
+                                invite_id: link_id,
+                            })
+                            .execute(&mut conn)
+                            .await
+                            .map_err(|e| match e {
+                                diesel::result::Error::DatabaseError(
+                                    diesel::result::DatabaseErrorKind::UniqueViolation,
+                                    ..,
+                                ) => TimeError::AlreadyRequested,
+                                e => e.into(),
+                            })?;
+
+                        return Ok(LeaderboardJoin::Requested(name));
+                    }
+
+                    if let Some(link_id) = link_id {
+                        use crate::schema::leaderboard_invites::dsl::*;
+
+                        let used = diesel::update(leaderboard_invites.find(link_id))
+                            .filter(max_uses.is_null().or(uses.lt(max_uses.assume_not_null())))
+                            .filter(expires_at.is_null().or(expires_at.gt(diesel::dsl::now)))
+                            .set(uses.eq(uses + 1))
+                            .execute(&mut conn)
+                            .await?;
+
+                        if used == 0 {
+                            return Err(TimeError::InviteExpired);
+                        }
+                    }
+
                     diesel::insert_into(leaderboard_members::table)
                         .values(&user)
                         .execute(&mut conn)
                         .await?;
 
-                    Ok::<i64, TimeError>(
-                        LeaderboardMember::belonging_to(&board)
-                            .count()
-                            .first::<i64>(&mut conn)
-                            .await?,
-                    )
+                    let member_count = LeaderboardMember::belonging_to(&board)
+                        .count()
+                        .first::<i64>(&mut conn)
+                        .await?;
+
+                    Ok::<LeaderboardJoin, TimeError>(LeaderboardJoin::Joined(
+                        crate::api::users::MinimalLeaderboard {
+                            name,
+                            member_count: member_count as i32,
+                        },
+                    ))
                 })
             })
-            .await?;
+            .await
+    }
 
-        Ok(crate::api::users::MinimalLeaderboard {
-            name,
-            member_count: member_count as i32,
-        })
+    pub async fn get_join_requests(&self, lid: i32) -> Result<Vec<JoinRequest>, TimeError> {
+        let mut conn = self.db.get().await?;
+
+        Ok(leaderboard_join_requests::table
+            .inner_join(user_identities::table)
+            .filter(leaderboard_join_requests::leaderboard_id.eq(lid))
+            .order_by(leaderboard_join_requests::creation_time)
+            .select((
+                user_identities::username,
+                leaderboard_join_requests::creation_time,
+            ))
+            .load::<JoinRequest>(&mut conn)
+            .await?)
+    }
+
+    /// Removes the join request of the user, adding them to the leaderboard if it was approved.
+    /// Returns whether there was a request.
+    pub async fn resolve_join_request(
+        &self,
+        lid: i32,
+        uid: i32,
+        approve: bool,
+    ) -> Result<bool, TimeError> {
+        let mut conn = self.db.get().await?;
+
+        conn.build_transaction()
+            .read_write()
+            .run(|mut conn| {
+                Box::pin(async move {
+                    let removed = diesel::delete(leaderboard_join_requests::table)
+                        .filter(leaderboard_join_requests::leaderboard_id.eq(lid))
+                        .filter(leaderboard_join_requests::user_id.eq(uid))
+                        .returning(leaderboard_join_requests::invite_id)
+                        .get_result::<Option<i32>>(&mut conn)
+                        .await
+                        .optional()?;
+
+                    let Some(link_id) = removed else {
+                        return Ok(false);
+                    };
+
+                    if approve {
+                        let added = diesel::insert_into(leaderboard_members::table)
+                            .values(NewLeaderboardMember {
+                                user_id: uid,
+                                leaderboard_id: lid,
+                                admin: false,
+                            })
+                            .on_conflict_do_nothing()
+                            .execute(&mut conn)
+                            .await?;
+
+                        // NOTE: The use is counted even if the link has been used up or has
+                        // expired since the request was made
+                        if let Some(link_id) = link_id.filter(|_| added != 0) {
+                            use crate::schema::leaderboard_invites::dsl::*;
+
+                            diesel::update(leaderboard_invites.find(link_id))
+                                .set(uses.eq(uses + 1))
+                                .execute(&mut conn)
+                                .await?;
+                        }
+                    }
+
+                    Ok::<bool, TimeError>(true)
+                })
+            })
+            .await
+    }
+
+    /// Bans the user from the leaderboard, removing their membership and join request
+    pub async fn ban_leaderboard_member(
+        &self,
+        lid: i32,
+        uid: i32,
+        banner: i32,
+    ) -> Result<(), TimeError> {
+        let mut conn = self.db.get().await?;
+
+        conn.build_transaction()
+            .read_write()
+            .run(|mut conn| {
+                Box::pin(async move {
+                    diesel::insert_into(leaderboard_bans::table)
+                        .values(NewLeaderboardBan {
+                            leaderboard_id: lid,
+                            user_id: uid,
+                            banned_by: Some(banner),
+                        })
+                        .on_conflict_do_nothing()
+                        .execute(&mut conn)
+                        .await?;
+
+                    diesel::delete(leaderboard_members::table)
+                        .filter(leaderboard_members::leaderboard_id.eq(lid))
+                        .filter(user_id.eq(uid))
+                        .execute(&mut conn)
+                        .await?;
+
+                    diesel::delete(leaderboard_join_requests::table)
+                        .filter(leaderboard_join_requests::leaderboard_id.eq(lid))
+                        .filter(leaderboard_join_requests::user_id.eq(uid))
+                        .execute(&mut conn)
+                        .await?;
+
+                    Ok::<(), TimeError>(())
+                })
+            })
+            .await
+    }
+
+    pub async fn unban_leaderboard_member(&self, lid: i32, uid: i32) -> Result<bool, TimeError> {
+        let mut conn = self.db.get().await?;
+
+        Ok(diesel::delete(leaderboard_bans::table)
+            .filter(leaderboard_bans::leaderboard_id.eq(lid))
+            .filter(leaderboard_bans::user_id.eq(uid))
+            .execute(&mut conn)
+            .await?
+            != 0)
+    }
+
+    pub async fn get_leaderboard_bans(&self, lid: i32) -> Result<Vec<LeaderboardBan>, TimeError> {
+        let mut conn = self.db.get().await?;
+
+        Ok(leaderboard_bans::table
+            .inner_join(
+                user_identities::table.on(user_identities::id.eq(leaderboard_bans::user_id)),
+            )
+            .filter(leaderboard_bans::leaderboard_id.eq(lid))
+            .order_by(leaderboard_bans::creation_time)
+            .select((user_identities::username, leaderboard_bans::creation_time))
+            .load::<LeaderboardBan>(&mut conn)
+            .await?)
+    }
+
+    /// Searches public leaderboards by name, the ones with the most members first
+    pub async fn search_public_leaderboards(
+        &self,
+        search: String,
+    ) -> Result<Vec<crate::api::users::MinimalLeaderboard>, TimeError> {
+        use diesel::dsl::count;
+
+        use crate::schema::leaderboards::dsl::*;
+
+        // NOTE: The search is matched literally, so the wildcards of LIKE are escaped
+        let search = search
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+
+        let mut conn = self.db.get().await?;
+
+        Ok(leaderboards
+            .inner_join(leaderboard_members::table)
+            .filter(public.eq(true))
+            .filter(name.ilike(format!("%{search}%")))
+            .group_by((id, name))
+            .select((name, count(user_id)))
+            .order_by((count(user_id).desc(), name))
+            .limit(100)
+            .load::<(String, i64)>(&mut conn)
+            .await?
+            .into_iter()
+            .map(
+                |(lname, member_count)| crate::api::users::MinimalLeaderboard {
+                    name: lname,
+                    member_count: member_count as i32,
+                },
+            )
+            .collect())
     }
 
     pub async fn remove_user_from_leaderboard(
@@ -287,6 +730,23 @@ impl super::DatabaseWrapper {
             .await?)
     }
 
+    pub async fn get_user_leaderboard_memberships(
+        &self,
+        uid: i32,
+    ) -> Result<Vec<(String, bool)>, TimeError> {
+        let mut conn = self.db.get().await?;
+
+        Ok(leaderboard_members::table
+            .inner_join(crate::schema::leaderboards::table)
+            .filter(user_id.eq(uid))
+            .select((
+                crate::schema::leaderboards::dsl::name,
+                leaderboard_members::dsl::admin,
+            ))
+            .load::<(String, bool)>(&mut conn)
+            .await?)
+    }
+
     pub async fn get_user_leaderboards(
         &self,
         uid: i32,
@@ -304,82 +764,75 @@ impl super::DatabaseWrapper {
             .load::<Leaderboard>(&mut conn)
             .await?;
 
-        let aweekago = NaiveDateTime::new(
-            Local::now().date_naive() - chrono::Duration::weeks(1),
-            chrono::NaiveTime::from_num_seconds_from_midnight_opt(0, 0).unwrap(),
-        );
-
-        // FIXME: We could maybe use limit here because we only need the
-        // user and the top member
-        let members = LeaderboardMember::belonging_to(&boards.iter().collect::<Vec<_>>())
-            //.inner_join(crate::schema::user_identities::table)
-            .left_join(
-                crate::schema::coding_activities::table.on(leaderboard_members::dsl::user_id
-                    .eq(coding_activities::dsl::user_id)
-                    .and(coding_activities::dsl::start_time.ge(aweekago))),
-            )
-            .group_by(leaderboard_members::dsl::id)
-            .select((
-                leaderboard_members::dsl::leaderboard_members::all_columns(),
-                // NOTE: This is a work-around (diesel has some issues with group_by)
-                diesel::dsl::sql::<diesel::sql_types::BigInt>(
-                    "COALESCE(SUM(coding_activities.duration), 0) AS coding_time",
-                ),
-            ))
-            .order_by(diesel::dsl::sql::<diesel::sql_types::BigInt>("coding_time").desc())
-            .load::<(LeaderboardMember, i64)>(&mut conn)
-            .await?;
-
-        let usernames = LeaderboardMember::belonging_to(&boards.iter().collect::<Vec<_>>())
-            .inner_join(crate::schema::user_identities::table)
-            .group_by(user_identities::dsl::id)
-            .select((user_identities::dsl::id, user_identities::dsl::username))
-            .load_stream::<(i32, String)>(&mut conn)
-            .await?
-            .try_fold(HashMap::new(), |mut acc, (id, name)| {
-                acc.insert(id, name);
-                futures_util::future::ready(Ok(acc))
-            })
-            .await?;
-
-        let members_populated = members
-            .iter()
-            .map(|(m, t)| {
-                (
-                    m.leaderboard_id,
-                    PrivateLeaderboardMember {
-                        id: m.user_id,
-                        username: usernames[&m.user_id].clone(),
-                        admin: m.admin,
-                        time_coded: *t as i32,
-                    },
-                )
-            })
-            .collect::<Vec<_>>();
-
-        Ok(boards
-            .iter()
-            .map(|b| {
-                let bms = members_populated
-                    .iter()
-                    .filter(|(lid, _)| *lid == b.id)
-                    .map(|(_, m)| m)
-                    .collect::<Vec<_>>();
-
-                let (my_position, me) = bms
-                    .iter()
-                    .enumerate()
-                    .find(|(_, m)| m.id == user.id)
-                    .expect("bug: impossible");
-                ListLeaderboard {
-                    name: b.name.clone(),
-                    member_count: bms.len() as i32,
-                    // NOTE: Sorted in the query
-                    top_member: bms[0].clone(),
-                    my_position: my_position as i32 + 1,
-                    me: (*me).clone(),
-                }
-            })
-            .collect::<Vec<_>>())
+        use diesel::sql_types::{Array, Integer, Nullable, Text, Timestamptz};
+
+        let timezone = user.tz();
+
+        #[derive(QueryableByName)]
+        struct RankedMember {
+            #[diesel(sql_type = diesel::sql_types::Integer)]
+            leaderboard_id: i32,
+            #[diesel(sql_type = diesel::sql_types::Integer)]
+            position: i32,
+            #[diesel(sql_type = diesel::sql_types::Integer)]
+            member_count: i32,
+            #[diesel(embed)]
+            member: PrivateLeaderboardMember,
+        }
+
+        // NOTE: Only the top member and the user are needed from each ranking
+        let ranked = diesel::sql_query(format!(
+            "{RANKING_QUERY} \
+             SELECT leaderboard_id, position, member_count, id, username, admin, time_coded, \
+             score FROM ranking \
+             WHERE position = 1 OR id = $5 \
+             ORDER BY leaderboard_id, position"
+        ))
+        .bind::<Array<Integer>, _>(boards.iter().map(|b| b.id).collect::<Vec<_>>())
+        .bind::<Array<Timestamptz>, _>(
+            boards
+                .iter()
+                .map(|b| b.time_window.start(b.window_days, timezone))
+                .collect::<Vec<_>>(),
+        )
+        .bind::<Array<Nullable<Timestamptz>>, _>(vec![
+            None::<chrono::DateTime<chrono::Utc>>;
+            boards.len()
+        ])
+        .bind::<Text, _>(timezone.name())
+        .bind::<Integer, _>(user.id)
+        .load::<RankedMember>(&mut conn)
+        .await?;
+
+        let mut rankings = HashMap::<i32, Vec<RankedMember>>::new();
+        for member in ranked {
+            rankings
+                .entry(member.leaderboard_id)
+                .or_default()
+                .push(member);
+        }
+
+        let mut list = Vec::new();
+        for board in boards {
+            let ranking = rankings.remove(&board.id).unwrap_or_default();
+
+            // NOTE: The user might have left the leaderboard after it was loaded
+            let (Some(me), Some(top)) = (
+                ranking.iter().find(|m| m.member.id == user.id),
+                ranking.iter().find(|m| m.position == 1),
+            ) else {
+                continue;
+            };
+
+            list.push(ListLeaderboard {
+                name: board.name,
+                member_count: me.member_count,
+                top_member: top.member.clone(),
+                my_position: me.position,
+                me: me.member.clone(),
+            });
+        }
+
+        Ok(list)
     }
 }
@@ -4,13 +4,36 @@ use diesel_async::RunQueryDsl;
 use crate::{error::TimeError, models::*};
 
 impl super::DatabaseWrapper {
-    pub async fn search_public_users(&self, search: String) -> Result<Vec<PublicUser>, TimeError> {
+    /// Searches public users by name. Users who have blocked the viewer or who the viewer
+    /// has blocked are not shown.
+    pub async fn search_public_users(
+        &self,
+        search: String,
+        viewer: Option<i32>,
+    ) -> Result<Vec<PublicUser>, TimeError> {
         let mut conn = self.db.get().await?;
 
-        use crate::schema::user_identities::dsl::*;
-        Ok(user_identities
+        use crate::schema::{user_blocks, user_identities::dsl::*};
+
+        let mut query = user_identities
             .filter(is_public.eq(true))
             .filter(username.like(format!("%{search}%")))
+            .into_boxed();
+
+        if let Some(viewer) = viewer {
+            query = query.filter(diesel::dsl::not(diesel::dsl::exists(
+                user_blocks::table.filter(
+                    user_blocks::blocker_id
+                        .eq(viewer)
+                        .and(user_blocks::blocked_id.eq(id))
+                        .or(user_blocks::blocker_id
+                            .eq(id)
+                            .and(user_blocks::blocked_id.eq(viewer))),
+                ),
+            )));
+        }
+
+        Ok(query
             .load::<UserIdentity>(&mut conn)
             .await?
             .into_iter()
@@ -18,6 +41,20 @@ impl super::DatabaseWrapper {
             .collect())
     }
 
+    /// Returns which of the users have public accounts
+    pub async fn get_public_user_ids(&self, users: Vec<i32>) -> Result<Vec<i32>, TimeError> {
+        let mut conn = self.db.get().await?;
+
+        use crate::schema::user_identities::dsl::*;
+
+        Ok(user_identities
+            .filter(id.eq_any(users))
+            .filter(is_public.eq(true))
+            .select(id)
+            .load::<i32>(&mut conn)
+            .await?)
+    }
+
     pub async fn get_total_user_count(&self) -> Result<u64, TimeError> {
         let mut conn = self.db.get().await?;
 
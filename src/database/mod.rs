@@ -12,10 +12,14 @@ use diesel_async::{
 use crate::error::TimeError;
 
 pub mod activity;
+pub mod aliases;
 pub mod auth;
 pub mod friends;
+pub mod goals;
 pub mod leaderboards;
 pub mod misc;
+pub mod privacy;
+pub mod seasons;
 
 type DatabaseConnection = Object<AsyncPgConnection>;
 
@@ -44,6 +48,14 @@ impl FromRequest for DatabaseWrapper {
     }
 }
 
+impl From<Data<Database>> for DatabaseWrapper {
+    fn from(db: Data<Database>) -> Self {
+        DatabaseWrapper {
+            db: db.into_inner(),
+        }
+    }
+}
+
 impl Database {
     async fn get(&self) -> Result<DatabaseConnection, TimeError> {
         Ok(self.backend.get().await?)
@@ -0,0 +1,73 @@
+use diesel::{insert_into, prelude::*};
+use diesel_async::RunQueryDsl;
+
+use crate::{
+    error::TimeError,
+    models::{FriendVisibility, PrivacySettings, Redaction},
+};
+
+impl super::DatabaseWrapper {
+    pub async fn get_privacy_settings(&self, user: i32) -> Result<PrivacySettings, TimeError> {
+        let mut conn = self.db.get().await?;
+
+        use crate::schema::privacy_settings::dsl::*;
+
+        Ok(privacy_settings
+            .find(user)
+            .first::<PrivacySettings>(&mut conn)
+            .await
+            .optional()?
+            .unwrap_or(PrivacySettings {
+                user_id: user,
+                ..Default::default()
+            }))
+    }
+
+    /// Returns the privacy settings of the users, users with the default settings may be missing
+    pub async fn get_privacy_settings_of_users(
+        &self,
+        users: Vec<i32>,
+    ) -> Result<Vec<PrivacySettings>, TimeError> {
+        let mut conn = self.db.get().await?;
+
+        use crate::schema::privacy_settings::dsl::*;
+
+        Ok(privacy_settings
+            .filter(user_id.eq_any(users))
+            .load::<PrivacySettings>(&mut conn)
+            .await?)
+    }
+
+    pub async fn set_privacy_settings(&self, settings: PrivacySettings) -> Result<(), TimeError> {
+        let mut conn = self.db.get().await?;
+
+        use crate::schema::privacy_settings::dsl::*;
+
+        insert_into(privacy_settings)
+            .values(&settings)
+            .on_conflict(user_id)
+            .do_update()
+            .set(&settings)
+            .execute(&mut conn)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Returns what is hidden from others when they view the activity of the user. The friend
+    /// viewing the activity should only be given if they are friends with the user.
+    pub async fn get_redaction(
+        &self,
+        user: i32,
+        friend: Option<i32>,
+    ) -> Result<Redaction, TimeError> {
+        let privacy = self.get_privacy_settings(user).await?;
+
+        let visibility: Option<FriendVisibility> = match friend {
+            Some(friend) => Some(self.get_friend_visibility(user, friend).await?),
+            None => None,
+        };
+
+        Ok(Redaction::new(&privacy, visibility.as_ref()))
+    }
+}
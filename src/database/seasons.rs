@@ -0,0 +1,164 @@
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+
+use crate::{error::TimeError, models::*, schema::leaderboard_season_rankings};
+
+impl super::DatabaseWrapper {
+    pub async fn get_all_leaderboards(&self) -> Result<Vec<Leaderboard>, TimeError> {
+        let mut conn = self.db.get().await?;
+
+        use crate::schema::leaderboards::dsl::*;
+        Ok(leaderboards.load::<Leaderboard>(&mut conn).await?)
+    }
+
+    /// Returns the end of the latest recorded season of the leaderboard
+    pub async fn get_last_season_end(&self, lid: i32) -> Result<Option<DateTime<Utc>>, TimeError> {
+        use crate::schema::leaderboard_seasons::dsl::*;
+
+        let mut conn = self.db.get().await?;
+
+        Ok(leaderboard_seasons
+            .filter(leaderboard_id.eq(lid))
+            .order_by(end_time.desc())
+            .select(end_time)
+            .first::<DateTime<Utc>>(&mut conn)
+            .await
+            .optional()?)
+    }
+
+    /// Records the final ranking of the leaderboard for the season, if it hasn't been
+    /// recorded yet. Returns whether a new season was recorded.
+    pub async fn save_leaderboard_season(
+        &self,
+        board: &Leaderboard,
+        season_period: String,
+        season_start: DateTime<Utc>,
+        season_end: DateTime<Utc>,
+    ) -> Result<bool, TimeError> {
+        use crate::schema::leaderboard_seasons::dsl::*;
+
+        let mut conn = self.db.get().await?;
+
+        let exists = diesel::select(diesel::dsl::exists(
+            leaderboard_seasons
+                .filter(leaderboard_id.eq(board.id))
+                .filter(period.eq(&season_period)),
+        ))
+        .get_result::<bool>(&mut conn)
+        .await?;
+
+        if exists {
+            return Ok(false);
+        }
+
+        let ranking = self
+            .get_leaderboard_ranking_between(board, season_start, Some(season_end), chrono_tz::UTC)
+            .await?;
+
+        let season = NewLeaderboardSeason {
+            leaderboard_id: board.id,
+            period: season_period,
+            start_time: season_start,
+            end_time: season_end,
+            metric: board.metric,
+        };
+
+        conn.build_transaction()
+            .read_write()
+            .run(|mut conn| {
+                Box::pin(async move {
+                    // NOTE: Another job might have recorded the season after the check above
+                    let season_id = match diesel::insert_into(leaderboard_seasons)
+                        .values(season)
+                        .on_conflict_do_nothing()
+                        .returning(id)
+                        .get_result::<i32>(&mut conn)
+                        .await
+                        .optional()?
+                    {
+                        Some(season_id) => season_id,
+                        None => return Ok(false),
+                    };
+
+                    let rankings = ranking
+                        .into_iter()
+                        .enumerate()
+                        .map(|(position, member)| SeasonRanking {
+                            season_id,
+                            position: position as i32 + 1,
+                            user_id: Some(member.id),
+                            username: member.username,
+                            time_coded: member.time_coded,
+                            score: member.score,
+                        })
+                        .collect::<Vec<_>>();
+
+                    diesel::insert_into(leaderboard_season_rankings::table)
+                        .values(&rankings)
+                        .execute(&mut conn)
+                        .await?;
+
+                    Ok::<bool, TimeError>(true)
+                })
+            })
+            .await
+    }
+
+    /// Returns the recorded seasons of the leaderboard and their winners, the latest first
+    pub async fn get_leaderboard_seasons(&self, lid: i32) -> Result<Vec<SeasonSummary>, TimeError> {
+        use crate::schema::leaderboard_seasons::dsl::*;
+
+        let mut conn = self.db.get().await?;
+
+        Ok(leaderboard_seasons
+            .left_join(
+                leaderboard_season_rankings::table.on(leaderboard_season_rankings::season_id
+                    .eq(id)
+                    .and(leaderboard_season_rankings::position.eq(1))),
+            )
+            .filter(leaderboard_id.eq(lid))
+            .order_by(start_time.desc())
+            .load::<(LeaderboardSeason, Option<SeasonRanking>)>(&mut conn)
+            .await?
+            .into_iter()
+            .map(|(season, winner)| SeasonSummary {
+                period: season.period,
+                start_time: season.start_time,
+                end_time: season.end_time,
+                metric: season.metric,
+                winner,
+            })
+            .collect())
+    }
+
+    pub async fn get_leaderboard_season(
+        &self,
+        lid: i32,
+        season_period: String,
+    ) -> Result<SeasonSnapshot, TimeError> {
+        use crate::schema::leaderboard_seasons::dsl::*;
+
+        let mut conn = self.db.get().await?;
+
+        let season = leaderboard_seasons
+            .filter(leaderboard_id.eq(lid))
+            .filter(period.eq(season_period))
+            .first::<LeaderboardSeason>(&mut conn)
+            .await?;
+
+        let members = leaderboard_season_rankings::table
+            .filter(leaderboard_season_rankings::season_id.eq(season.id))
+            .order_by(leaderboard_season_rankings::position)
+            .load::<SeasonRanking>(&mut conn)
+            .await?;
+
+        Ok(SeasonSnapshot {
+            period: season.period,
+            start_time: season.start_time,
+            end_time: season.end_time,
+            metric: season.metric,
+            members,
+        })
+    }
+}
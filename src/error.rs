@@ -25,6 +25,8 @@ pub enum TimeError {
     LeaderboardExists,
     #[error("Leaderboard not found")]
     LeaderboardNotFound,
+    #[error("Season not found")]
+    SeasonNotFound,
     #[error("You are not authorized")]
     Unauthorized,
     #[error("Missing secured access token")]
@@ -43,12 +45,42 @@ pub enum TimeError {
     BadId,
     #[error("Already friends")]
     AlreadyFriends,
+    #[error("Friend request already sent")]
+    FriendRequestExists,
+    #[error("You're not friends")]
+    NotFriends,
+    #[error("Friend request not found")]
+    FriendRequestNotFound,
     #[error("You're already a member")]
     AlreadyMember,
+    #[error("The invite has expired or has been used up")]
+    InviteExpired,
+    #[error("Invite exists")]
+    InviteExists,
+    #[error("You have already requested to join")]
+    AlreadyRequested,
+    #[error("Join request not found")]
+    JoinRequestNotFound,
+    #[error("Invite not found")]
+    InviteNotFound,
+    #[error("You are banned from this leaderboard")]
+    BannedFromLeaderboard,
+    #[error("Ban not found")]
+    BanNotFound,
+    #[error("An alias for this pattern exists")]
+    AliasExists,
+    #[error("Invalid alias pattern: {0}")]
+    BadAliasPattern(String),
+    #[error("At least one filter is required")]
+    MissingFilter,
+    #[error("At least one field has to be changed")]
+    NoChanges,
     #[error("You're not a member")]
     NotMember,
     #[error("There are no more admins left, you cannot leave")]
     LastAdmin,
+    #[error("You are the owner, transfer the ownership before leaving")]
+    OwnerCannotLeave,
     #[error("Bad code")]
     BadCode,
     #[error("Literally no idea how this happened")]
@@ -57,6 +89,18 @@ pub enum TimeError {
     TooManyRegisters,
     #[error("The user has no active session")]
     NotActive,
+    #[error("Heartbeat timestamp is in the future")]
+    FutureHeartBeat,
+    #[error("Invalid timestamp")]
+    InvalidTimestamp,
+    #[error("Activity ends in the future")]
+    FutureActivity,
+    #[error("Activity overlaps another activity")]
+    OverlappingActivity,
+    #[error("Unknown timezone")]
+    BadTimezone,
+    #[error("The avatar url has to be a http or https url")]
+    BadAvatarUrl,
 }
 
 unsafe impl Send for TimeError {}
@@ -65,19 +109,41 @@ impl ResponseError for TimeError {
     fn status_code(&self) -> StatusCode {
         error!("{}", self);
         match self {
-            TimeError::UserNotFound | TimeError::LeaderboardNotFound | TimeError::NotActive => {
-                StatusCode::NOT_FOUND
-            }
+            TimeError::UserNotFound
+            | TimeError::LeaderboardNotFound
+            | TimeError::SeasonNotFound
+            | TimeError::InviteNotFound
+            | TimeError::JoinRequestNotFound
+            | TimeError::BanNotFound
+            | TimeError::FriendRequestNotFound
+            | TimeError::NotActive => StatusCode::NOT_FOUND,
             TimeError::BadUsername
             | TimeError::InvalidLength(_)
             | TimeError::BadId
-            | TimeError::BadLeaderboardName => StatusCode::BAD_REQUEST,
-            TimeError::CurrentUser | TimeError::NotMember | TimeError::LastAdmin => {
-                StatusCode::FORBIDDEN
-            }
+            | TimeError::BadLeaderboardName
+            | TimeError::FutureHeartBeat
+            | TimeError::InvalidTimestamp
+            | TimeError::FutureActivity
+            | TimeError::BadAliasPattern(_)
+            | TimeError::MissingFilter
+            | TimeError::NoChanges
+            | TimeError::BadTimezone
+            | TimeError::BadAvatarUrl => StatusCode::BAD_REQUEST,
+            TimeError::CurrentUser
+            | TimeError::NotMember
+            | TimeError::LastAdmin
+            | TimeError::OwnerCannotLeave
+            | TimeError::NotFriends
+            | TimeError::InviteExpired
+            | TimeError::BannedFromLeaderboard => StatusCode::FORBIDDEN,
             TimeError::AlreadyFriends
             | TimeError::LeaderboardExists
             | TimeError::AlreadyMember
+            | TimeError::InviteExists
+            | TimeError::AlreadyRequested
+            | TimeError::FriendRequestExists
+            | TimeError::AliasExists
+            | TimeError::OverlappingActivity
             | TimeError::UserExists => StatusCode::CONFLICT,
             TimeError::Unauthorized
             | TimeError::InvalidCredentials
@@ -28,7 +28,7 @@ use auth::{secured_access::SecuredAccessTokenStorage, AuthMiddleware, Authentica
 use awc::Client;
 use chrono::NaiveDateTime;
 use dashmap::DashMap;
-use database::Database;
+use database::{Database, DatabaseWrapper};
 use governor::{Quota, RateLimiter};
 use ratelimiter::TestaustimeRateLimiter;
 use serde_derive::Deserialize;
@@ -55,6 +55,24 @@ pub struct TimeConfig {
     pub address: String,
     pub database_url: String,
     pub allowed_origin: String,
+    #[serde(default = "default_session_flush_interval")]
+    pub session_flush_interval: u64,
+    #[serde(default = "default_session_idle_timeout")]
+    pub session_idle_timeout: i64,
+    #[serde(default = "default_season_snapshot_interval")]
+    pub season_snapshot_interval: u64,
+}
+
+fn default_session_flush_interval() -> u64 {
+    60
+}
+
+fn default_session_idle_timeout() -> i64 {
+    900
+}
+
+fn default_season_snapshot_interval() -> u64 {
+    3600
 }
 
 pub struct TestaustimeRootSpanBuilder;
@@ -87,6 +105,20 @@ async fn main() -> std::io::Result<()> {
         toml::from_str(&std::fs::read_to_string("settings.toml").expect("Missing settings.toml"))
             .expect("Invalid Toml in settings.toml");
 
+    // NOTE: Zero length intervals would make the background jobs panic
+    assert!(
+        config.session_flush_interval >= 1,
+        "session_flush_interval in settings.toml has to be at least 1 second"
+    );
+    assert!(
+        config.session_idle_timeout >= 1,
+        "session_idle_timeout in settings.toml has to be at least 1 second"
+    );
+    assert!(
+        config.season_snapshot_interval >= 1,
+        "season_snapshot_interval in settings.toml has to be at least 1 second"
+    );
+
     let database = Data::new(Database::new(config.database_url));
 
     let register_limiter = Data::new(RegisterLimiter {
@@ -103,8 +135,47 @@ async fn main() -> std::io::Result<()> {
 
     let heartbeat_store = Data::new(api::activity::HeartBeatMemoryStore::new());
 
+    let alias_cache = Data::new(api::activity::ProjectAliasCache::new());
+
+    let session_idle_timeout = Data::new(api::activity::SessionIdleTimeout(
+        chrono::Duration::seconds(config.session_idle_timeout),
+    ));
+
     let secured_access_token_storage = Data::new(SecuredAccessTokenStorage::new());
 
+    {
+        let db = DatabaseWrapper::from(Data::clone(&database));
+        let heartbeats = Data::clone(&heartbeat_store);
+        let max_idle = session_idle_timeout.0;
+        let mut interval = actix_web::rt::time::interval(std::time::Duration::from_secs(
+            config.session_flush_interval,
+        ));
+
+        actix_web::rt::spawn(async move {
+            loop {
+                interval.tick().await;
+                api::activity::flush_idle_heartbeats(&db, &heartbeats, max_idle).await;
+            }
+        });
+    }
+
+    {
+        let db = DatabaseWrapper::from(Data::clone(&database));
+        let mut interval = actix_web::rt::time::interval(std::time::Duration::from_secs(
+            config.season_snapshot_interval,
+        ));
+
+        actix_web::rt::spawn(async move {
+            loop {
+                interval.tick().await;
+                api::leaderboards::snapshot_leaderboard_seasons(&db, chrono::Utc::now()).await;
+            }
+        });
+    }
+
+    let shutdown_database = Data::clone(&database);
+    let shutdown_heartbeat_store = Data::clone(&heartbeat_store);
+
     HttpServer::new(move || {
         #[cfg(feature = "testausid")]
         let tracing = TracingLogger::<TestaustimeRootSpanBuilder>::new();
@@ -126,6 +197,7 @@ async fn main() -> std::io::Result<()> {
             .app_data(Data::clone(&register_limiter))
             .app_data(query_config)
             .app_data(Data::clone(&secured_access_token_storage))
+            .app_data(Data::clone(&session_idle_timeout))
             .wrap(cors)
             .service(api::health)
             .service(api::auth::register)
@@ -141,9 +213,18 @@ async fn main() -> std::io::Result<()> {
                     .service({
                         web::scope("/activity")
                             .service(api::activity::update)
+                            .service(api::activity::batch)
                             .service(api::activity::delete)
+                            .service(api::activity::bulk_delete)
+                            .service(api::activity::bulk_update)
+                            .service(api::activity::add_manual_activity)
+                            .service(api::activity::edit_manual_activity)
+                            .service(api::activity::delete_manual_activity)
                             .service(api::activity::flush)
                             .service(api::activity::rename_project)
+                            .service(api::activity::get_aliases)
+                            .service(api::activity::add_alias)
+                            .service(api::activity::delete_alias)
                     })
                     .service(api::auth::login)
                     .service(api::auth::regenerate)
@@ -151,8 +232,16 @@ async fn main() -> std::io::Result<()> {
                     .service(api::auth::changepassword)
                     .service(api::auth::get_secured_access_token)
                     .service(api::account::change_settings)
+                    .service(api::account::get_privacy_settings)
+                    .service(api::account::change_privacy_settings)
                     .service(api::friends::add_friend)
                     .service(api::friends::get_friends)
+                    .service(api::friends::get_friend_requests)
+                    .service(api::friends::accept_friend_request)
+                    .service(api::friends::decline_friend_request)
+                    .service(api::friends::block_friend_request)
+                    .service(api::friends::get_friend_visibility)
+                    .service(api::friends::set_friend_visibility)
                     .service(api::friends::regenerate_friend_code)
                     .service(api::friends::remove)
                     .service(api::users::my_profile)
@@ -160,18 +249,45 @@ async fn main() -> std::io::Result<()> {
                     .service(api::users::get_current_activity)
                     .service(api::users::delete_user)
                     .service(api::users::my_leaderboards)
+                    .service(api::users::get_blocked_users)
+                    .service(api::users::block_user)
+                    .service(api::users::unblock_user)
                     .service(api::users::get_activity_summary)
+                    .service(api::users::get_activity_aggregate)
+                    .service(api::users::get_goals)
+                    .service(api::users::set_goals)
+                    .service(api::users::get_streak)
+                    .service(api::users::export_data)
                     .service(api::leaderboards::create_leaderboard)
+                    .service(api::leaderboards::search_leaderboards)
                     .service(api::leaderboards::get_leaderboard)
                     .service(api::leaderboards::join_leaderboard)
+                    .service(api::leaderboards::join_public_leaderboard)
                     .service(api::leaderboards::leave_leaderboard)
                     .service(api::leaderboards::delete_leaderboard)
                     .service(api::leaderboards::promote_member)
                     .service(api::leaderboards::demote_member)
                     .service(api::leaderboards::kick_member)
                     .service(api::leaderboards::regenerate_invite)
+                    .service(api::leaderboards::rename_leaderboard)
+                    .service(api::leaderboards::transfer_ownership)
+                    .service(api::leaderboards::create_invite_link)
+                    .service(api::leaderboards::get_invite_links)
+                    .service(api::leaderboards::delete_invite_link)
+                    .service(api::leaderboards::get_join_requests)
+                    .service(api::leaderboards::approve_join_request)
+                    .service(api::leaderboards::reject_join_request)
+                    .service(api::leaderboards::ban_member)
+                    .service(api::leaderboards::get_bans)
+                    .service(api::leaderboards::unban_member)
+                    .service(api::leaderboards::change_settings)
+                    .service(api::leaderboards::get_seasons)
+                    .service(api::leaderboards::get_season)
                     .service(api::search::search_public_users)
-                    .service(api::stats::stats);
+                    .service(api::stats::stats)
+                    .service(api::wakatime::send_heartbeat)
+                    .service(api::wakatime::send_heartbeats_bulk)
+                    .service(api::wakatime::import);
                 #[cfg(feature = "testausid")]
                 {
                     scope.service(api::oauth::callback)
@@ -182,7 +298,8 @@ async fn main() -> std::io::Result<()> {
                 }
             })
             .app_data(Data::clone(&database))
-            .app_data(Data::clone(&heartbeat_store));
+            .app_data(Data::clone(&heartbeat_store))
+            .app_data(Data::clone(&alias_cache));
         #[cfg(feature = "testausid")]
         {
             app.app_data(Data::new(client))
@@ -194,5 +311,17 @@ async fn main() -> std::io::Result<()> {
     })
     .bind(config.address)?
     .run()
-    .await
+    .await?;
+
+    info!(
+        "Flushing {} open sessions before exiting",
+        shutdown_heartbeat_store.len()
+    );
+    api::activity::flush_all_heartbeats(
+        &DatabaseWrapper::from(shutdown_database),
+        &shutdown_heartbeat_store,
+    )
+    .await;
+
+    Ok(())
 }
@@ -16,6 +16,14 @@ pub struct UserIdentity {
     pub username: String,
     pub registration_time: chrono::NaiveDateTime,
     pub is_public: bool,
+    pub timezone: String,
+}
+
+impl UserIdentity {
+    /// The timezone of the user, the name is validated when the user sets it
+    pub fn tz(&self) -> chrono_tz::Tz {
+        self.timezone.parse().unwrap_or(chrono_tz::UTC)
+    }
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -47,7 +55,10 @@ pub struct TestaustimeUser {
     pub identity: i32,
 }
 
-use crate::{requests::HeartBeat, schema::testaustime_users};
+use crate::{
+    requests::{AggregateField, DataRequest, HeartBeat},
+    schema::testaustime_users,
+};
 
 #[derive(Insertable, Serialize, Clone)]
 #[diesel(table_name = testaustime_users)]
@@ -67,6 +78,7 @@ pub struct SelfUser {
     pub username: String,
     pub registration_time: chrono::NaiveDateTime,
     pub is_public: bool,
+    pub timezone: String,
 }
 
 impl From<UserIdentity> for SelfUser {
@@ -78,6 +90,7 @@ impl From<UserIdentity> for SelfUser {
             username: u.username,
             registration_time: u.registration_time,
             is_public: u.is_public,
+            timezone: u.timezone,
         }
     }
 }
@@ -123,6 +136,11 @@ pub struct FriendRelation {
     pub id: i32,
     pub lesser_id: i32,
     pub greater_id: i32,
+    /// The user who sent the friend request, `None` for friends added before requests existed
+    pub requester_id: Option<i32>,
+    /// Pending friend requests are not accepted yet
+    pub accepted: bool,
+    pub creation_time: chrono::DateTime<chrono::Utc>,
 }
 
 use crate::schema::friend_relations;
@@ -132,6 +150,164 @@ use crate::schema::friend_relations;
 pub struct NewFriendRelation {
     pub lesser_id: i32,
     pub greater_id: i32,
+    pub requester_id: Option<i32>,
+    pub accepted: bool,
+}
+
+/// How the user responds to a friend request they have received
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FriendRequestResponse {
+    Accept,
+    Decline,
+    /// Declines the request and blocks the requester
+    Block,
+}
+
+#[derive(Queryable, Serialize, Deserialize, Clone, Debug)]
+pub struct FriendRequest {
+    pub username: String,
+    pub creation_time: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FriendRequests {
+    /// Requests sent to the user
+    pub incoming: Vec<FriendRequest>,
+    /// Requests the user has sent
+    pub outgoing: Vec<FriendRequest>,
+}
+
+use crate::schema::user_blocks;
+
+#[derive(Insertable)]
+#[diesel(table_name = user_blocks)]
+pub struct NewUserBlock {
+    pub blocker_id: i32,
+    pub blocked_id: i32,
+}
+
+#[derive(Queryable, Serialize, Deserialize, Clone, Debug)]
+pub struct BlockedUser {
+    pub username: String,
+    pub creation_time: chrono::DateTime<chrono::Utc>,
+}
+
+use crate::schema::friend_visibility;
+
+/// What a user shares with one of their friends, everything is shared by default
+#[derive(
+    Queryable, Insertable, AsChangeset, Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq,
+)]
+#[diesel(table_name = friend_visibility)]
+pub struct FriendVisibility {
+    /// The user whose data is shared
+    #[serde(skip)]
+    pub user_id: i32,
+    /// The friend who sees the data
+    #[serde(skip)]
+    pub friend_id: i32,
+    pub hide_project_names: bool,
+    pub hide_hostnames: bool,
+    pub hide_editors: bool,
+}
+
+use crate::schema::privacy_settings;
+
+/// What a user hides from everyone else, the user always sees all of their own data
+#[derive(Queryable, Insertable, AsChangeset, Serialize, Deserialize, Clone, Debug, Default)]
+#[diesel(table_name = privacy_settings)]
+pub struct PrivacySettings {
+    #[serde(skip)]
+    pub user_id: i32,
+    pub hide_project_names: bool,
+    pub hide_hostnames: bool,
+    pub hide_editors: bool,
+    /// Names of projects that are hidden even if project names are shown
+    pub private_projects: Vec<String>,
+}
+
+/// What is hidden from someone viewing the activity of another user, combined from the
+/// privacy settings of the user and what they share with the viewer if they are friends.
+/// Hidden values are replaced with placeholders like "private project".
+#[derive(Clone, Debug, Default)]
+pub struct Redaction {
+    pub hide_project_names: bool,
+    pub hide_hostnames: bool,
+    pub hide_editors: bool,
+    pub private_projects: Vec<String>,
+}
+
+impl Redaction {
+    pub fn new(privacy: &PrivacySettings, visibility: Option<&FriendVisibility>) -> Self {
+        Redaction {
+            hide_project_names: privacy.hide_project_names
+                || visibility.is_some_and(|v| v.hide_project_names),
+            hide_hostnames: privacy.hide_hostnames || visibility.is_some_and(|v| v.hide_hostnames),
+            hide_editors: privacy.hide_editors || visibility.is_some_and(|v| v.hide_editors),
+            private_projects: privacy
+                .private_projects
+                .iter()
+                .map(|project| project.to_lowercase())
+                .collect(),
+        }
+    }
+
+    pub fn placeholder(field: AggregateField) -> &'static str {
+        match field {
+            AggregateField::Project => "private project",
+            AggregateField::Hostname => "private host",
+            AggregateField::Editor => "private editor",
+            AggregateField::Language => "private language",
+        }
+    }
+
+    /// Whether all values of the field are hidden
+    pub fn hides(&self, field: AggregateField) -> bool {
+        match field {
+            AggregateField::Project => self.hide_project_names,
+            AggregateField::Hostname => self.hide_hostnames,
+            AggregateField::Editor => self.hide_editors,
+            AggregateField::Language => false,
+        }
+    }
+
+    pub fn is_private_project(&self, project: &str) -> bool {
+        self.private_projects.contains(&project.to_lowercase())
+    }
+
+    pub fn redact(&self, field: AggregateField, value: Option<String>) -> Option<String> {
+        match value {
+            Some(_) if self.hides(field) => Some(Self::placeholder(field).to_string()),
+            Some(project)
+                if field == AggregateField::Project && self.is_private_project(&project) =>
+            {
+                Some(Self::placeholder(field).to_string())
+            }
+            value => value,
+        }
+    }
+
+    /// Whether the request filters activities by a hidden value, which would reveal it
+    pub fn hides_filter(&self, request: &DataRequest) -> bool {
+        request.project_name.as_deref().is_some_and(|project| {
+            self.hides(AggregateField::Project) || self.is_private_project(project)
+        }) || (request.hostname.is_some() && self.hides(AggregateField::Hostname))
+            || (request.editor_name.is_some() && self.hides(AggregateField::Editor))
+    }
+
+    pub fn redact_heartbeat(&self, mut heartbeat: HeartBeat) -> HeartBeat {
+        heartbeat.project_name = self.redact(AggregateField::Project, heartbeat.project_name);
+        heartbeat.hostname = self.redact(AggregateField::Hostname, heartbeat.hostname);
+        heartbeat.editor_name = self.redact(AggregateField::Editor, heartbeat.editor_name);
+        heartbeat
+    }
+
+    pub fn redact_activity(&self, mut activity: CodingActivity) -> CodingActivity {
+        activity.project_name = self.redact(AggregateField::Project, activity.project_name);
+        activity.hostname = self.redact(AggregateField::Hostname, activity.hostname);
+        activity.editor_name = self.redact(AggregateField::Editor, activity.editor_name);
+        activity
+    }
 }
 
 #[derive(Queryable, Clone, Debug, Serialize, Identifiable, Associations)]
@@ -141,12 +317,66 @@ pub struct CodingActivity {
     pub id: i32,
     #[serde(skip_serializing)]
     pub user_id: i32,
-    pub start_time: chrono::NaiveDateTime,
+    pub start_time: chrono::DateTime<chrono::Utc>,
     pub duration: i32,
     pub project_name: Option<String>,
     pub language: Option<String>,
     pub editor_name: Option<String>,
     pub hostname: Option<String>,
+    #[diesel(deserialize_as = String)]
+    pub source: ActivitySource,
+}
+
+/// How an activity was recorded
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, Hash, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivitySource {
+    /// From the heartbeats of an editor plugin or an import
+    #[default]
+    Editor,
+    /// Entered by the user, e.g. for pair programming or whiteboard sessions
+    Manual,
+}
+
+impl ActivitySource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ActivitySource::Editor => "editor",
+            ActivitySource::Manual => "manual",
+        }
+    }
+}
+
+impl From<ActivitySource> for String {
+    fn from(source: ActivitySource) -> String {
+        source.as_str().to_string()
+    }
+}
+
+impl TryFrom<String> for ActivitySource {
+    type Error = String;
+
+    fn try_from(source: String) -> Result<Self, Self::Error> {
+        serde_json::from_value(serde_json::Value::String(source))
+            .map_err(|e| format!("Invalid activity source: {e}"))
+    }
+}
+
+/// Total coding time of a group of activities. Fields that were not grouped by are `None`.
+#[derive(QueryableByName, Clone, Debug)]
+pub struct ActivityAggregate {
+    #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Timestamptz>)]
+    pub bucket: Option<chrono::DateTime<chrono::Utc>>,
+    #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Text>)]
+    pub project_name: Option<String>,
+    #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Text>)]
+    pub language: Option<String>,
+    #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Text>)]
+    pub editor_name: Option<String>,
+    #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Text>)]
+    pub hostname: Option<String>,
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub total: i64,
 }
 
 use crate::schema::coding_activities;
@@ -155,20 +385,387 @@ use crate::schema::coding_activities;
 #[diesel(table_name = coding_activities)]
 pub struct NewCodingActivity {
     pub user_id: i32,
-    pub start_time: chrono::NaiveDateTime,
+    pub start_time: chrono::DateTime<chrono::Utc>,
+    pub duration: i32,
+    pub project_name: Option<String>,
+    pub language: Option<String>,
+    pub editor_name: Option<String>,
+    pub hostname: Option<String>,
+    #[diesel(serialize_as = String)]
+    pub source: ActivitySource,
+}
+
+/// The fields of a manual activity that the user can edit
+#[derive(AsChangeset, Clone, Debug)]
+#[diesel(table_name = coding_activities, treat_none_as_null = true)]
+pub struct ManualActivity {
+    pub start_time: chrono::DateTime<chrono::Utc>,
     pub duration: i32,
     pub project_name: Option<String>,
     pub language: Option<String>,
+}
+
+/// New values for the fields of activities, missing fields are left unchanged
+#[derive(AsChangeset, Deserialize, Clone, Debug, Default)]
+#[diesel(table_name = coding_activities)]
+pub struct ActivityChanges {
+    pub project_name: Option<String>,
+    pub language: Option<String>,
     pub editor_name: Option<String>,
     pub hostname: Option<String>,
 }
 
+impl ActivityChanges {
+    pub fn is_empty(&self) -> bool {
+        self.project_name.is_none()
+            && self.language.is_none()
+            && self.editor_name.is_none()
+            && self.hostname.is_none()
+    }
+}
+
+/// How the pattern of a project alias is matched against project names
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, Hash, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AliasMatch {
+    /// The whole project name is the pattern
+    #[default]
+    Exact,
+    /// The pattern may contain `*` matching any characters and `?` matching one character
+    Glob,
+    /// The pattern is a regular expression that has to match the whole project name
+    Regex,
+}
+
+impl AliasMatch {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AliasMatch::Exact => "exact",
+            AliasMatch::Glob => "glob",
+            AliasMatch::Regex => "regex",
+        }
+    }
+
+    pub fn compile(&self, pattern: &str) -> Result<regex::Regex, regex::Error> {
+        let expression = match self {
+            AliasMatch::Exact => regex::escape(pattern),
+            AliasMatch::Glob => pattern
+                .chars()
+                .map(|c| match c {
+                    '*' => String::from(".*"),
+                    '?' => String::from("."),
+                    c => regex::escape(&c.to_string()),
+                })
+                .collect(),
+            AliasMatch::Regex => pattern.to_string(),
+        };
+
+        regex::Regex::new(&format!("^(?:{expression})$"))
+    }
+}
+
+impl From<AliasMatch> for String {
+    fn from(kind: AliasMatch) -> String {
+        kind.as_str().to_string()
+    }
+}
+
+impl TryFrom<String> for AliasMatch {
+    type Error = String;
+
+    fn try_from(kind: String) -> Result<Self, Self::Error> {
+        serde_json::from_value(serde_json::Value::String(kind))
+            .map_err(|e| format!("Invalid alias match kind: {e}"))
+    }
+}
+
+/// Renames projects matching the pattern to the target when heartbeats arrive
+#[derive(Queryable, Serialize, Deserialize, Clone, Debug)]
+pub struct ProjectAlias {
+    pub id: i32,
+    pub pattern: String,
+    #[diesel(deserialize_as = String)]
+    pub match_kind: AliasMatch,
+    pub target: String,
+    pub creation_time: chrono::DateTime<chrono::Utc>,
+}
+
+use crate::schema::project_aliases;
+
+#[derive(Insertable)]
+#[diesel(table_name = project_aliases)]
+pub struct NewProjectAlias {
+    pub user_id: i32,
+    pub pattern: String,
+    #[diesel(serialize_as = String)]
+    pub match_kind: AliasMatch,
+    pub target: String,
+}
+
+/// The compiled alias rules of a user. The first matching rule in the order they were
+/// added is used.
+#[derive(Clone, Debug, Default)]
+pub struct ProjectAliasRules(Vec<(regex::Regex, String)>);
+
+impl ProjectAliasRules {
+    /// Rules are validated when they are added, rules that fail to compile are skipped
+    pub fn new(aliases: &[ProjectAlias]) -> Self {
+        ProjectAliasRules(
+            aliases
+                .iter()
+                .filter_map(|alias| {
+                    let regex = alias.match_kind.compile(&alias.pattern).ok()?;
+                    Some((regex, alias.target.clone()))
+                })
+                .collect(),
+        )
+    }
+
+    pub fn apply(&self, project: String) -> String {
+        self.0
+            .iter()
+            .find(|(regex, _)| regex.is_match(&project))
+            .map_or(project, |(_, target)| target.clone())
+    }
+
+    pub fn apply_heartbeat(&self, mut heartbeat: HeartBeat) -> HeartBeat {
+        heartbeat.project_name = heartbeat.project_name.map(|project| self.apply(project));
+        heartbeat
+    }
+}
+
+use crate::schema::coding_goals;
+
+/// Daily and weekly coding time goals of a user in seconds
+#[derive(Queryable, Insertable, AsChangeset, Clone, Debug, Default, Serialize)]
+#[diesel(table_name = coding_goals, treat_none_as_null = true)]
+pub struct CodingGoals {
+    #[serde(skip_serializing)]
+    pub user_id: i32,
+    pub daily_goal: Option<i32>,
+    pub weekly_goal: Option<i32>,
+}
+
+/// The period of time a leaderboard is ranked by
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, Hash, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LeaderboardWindow {
+    /// Since midnight today
+    Day,
+    /// Since midnight a week ago
+    #[default]
+    Week,
+    /// Since midnight 30 days ago
+    Month,
+    AllTime,
+    /// The last `window_days` days counting from now
+    Rolling,
+    /// Since the start of the current week, month or year
+    CalendarWeek,
+    CalendarMonth,
+    CalendarYear,
+}
+
+impl LeaderboardWindow {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LeaderboardWindow::Day => "day",
+            LeaderboardWindow::Week => "week",
+            LeaderboardWindow::Month => "month",
+            LeaderboardWindow::AllTime => "all_time",
+            LeaderboardWindow::Rolling => "rolling",
+            LeaderboardWindow::CalendarWeek => "calendar_week",
+            LeaderboardWindow::CalendarMonth => "calendar_month",
+            LeaderboardWindow::CalendarYear => "calendar_year",
+        }
+    }
+
+    /// Returns the start of the window in the given timezone
+    pub fn start(
+        &self,
+        window_days: Option<i32>,
+        timezone: chrono_tz::Tz,
+    ) -> chrono::DateTime<chrono::Utc> {
+        use chrono::{Datelike, Duration, TimeZone, Utc};
+
+        use crate::utils::{days_ago_at_midnight, midnight, start_of_week};
+
+        let today = Utc::now().with_timezone(&timezone).date_naive();
+        match self {
+            LeaderboardWindow::Day => days_ago_at_midnight(timezone, 0),
+            LeaderboardWindow::Week => days_ago_at_midnight(timezone, 7),
+            LeaderboardWindow::Month => days_ago_at_midnight(timezone, 30),
+            LeaderboardWindow::AllTime => Utc.timestamp_opt(0, 0).unwrap(),
+            LeaderboardWindow::Rolling => {
+                Utc::now() - Duration::days(window_days.unwrap_or(7).into())
+            }
+            LeaderboardWindow::CalendarWeek => start_of_week(timezone),
+            LeaderboardWindow::CalendarMonth => midnight(timezone, today.with_day(1).unwrap()),
+            LeaderboardWindow::CalendarYear => midnight(timezone, today.with_ordinal(1).unwrap()),
+        }
+    }
+
+    /// The length of the seasons recorded for leaderboards with this window
+    pub fn season_period(&self) -> SeasonPeriod {
+        match self {
+            LeaderboardWindow::Day => SeasonPeriod::Day,
+            LeaderboardWindow::Month | LeaderboardWindow::CalendarMonth => SeasonPeriod::Month,
+            LeaderboardWindow::CalendarYear => SeasonPeriod::Year,
+            _ => SeasonPeriod::Week,
+        }
+    }
+}
+
+/// The length of a leaderboard season
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SeasonPeriod {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl SeasonPeriod {
+    /// Returns the name, start and end of the last season that ended before `now`.
+    /// Seasons are calculated in UTC, the names are e.g. `2023-12-24`, `2023-W51`,
+    /// `2023-12` or `2023`.
+    pub fn last_ended(
+        &self,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> (
+        String,
+        chrono::DateTime<chrono::Utc>,
+        chrono::DateTime<chrono::Utc>,
+    ) {
+        use chrono::{Datelike, Duration, NaiveDate};
+
+        use crate::utils::midnight;
+
+        let today = now.date_naive();
+        let (start, end) = match self {
+            SeasonPeriod::Day => (today - Duration::days(1), today),
+            SeasonPeriod::Week => {
+                let end = today - Duration::days(today.weekday().num_days_from_monday().into());
+                (end - Duration::days(7), end)
+            }
+            SeasonPeriod::Month => {
+                let end = today.with_day(1).unwrap();
+                ((end - Duration::days(1)).with_day(1).unwrap(), end)
+            }
+            SeasonPeriod::Year => {
+                let end = today.with_ordinal(1).unwrap();
+                (NaiveDate::from_ymd_opt(end.year() - 1, 1, 1).unwrap(), end)
+            }
+        };
+
+        let name = match self {
+            SeasonPeriod::Day => start.format("%Y-%m-%d").to_string(),
+            SeasonPeriod::Week => start.format("%G-W%V").to_string(),
+            SeasonPeriod::Month => start.format("%Y-%m").to_string(),
+            SeasonPeriod::Year => start.format("%Y").to_string(),
+        };
+
+        (
+            name,
+            midnight(chrono_tz::UTC, start),
+            midnight(chrono_tz::UTC, end),
+        )
+    }
+}
+
+impl From<LeaderboardWindow> for String {
+    fn from(window: LeaderboardWindow) -> String {
+        window.as_str().to_string()
+    }
+}
+
+impl TryFrom<String> for LeaderboardWindow {
+    type Error = String;
+
+    fn try_from(window: String) -> Result<Self, Self::Error> {
+        serde_json::from_value(serde_json::Value::String(window))
+            .map_err(|e| format!("Invalid leaderboard window: {e}"))
+    }
+}
+
+/// What the members of a leaderboard are ranked by
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, Hash, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LeaderboardMetric {
+    /// Total coding time
+    #[default]
+    Time,
+    /// Number of days with any coding
+    ActiveDays,
+    /// Number of distinct projects
+    Projects,
+}
+
+impl LeaderboardMetric {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LeaderboardMetric::Time => "time",
+            LeaderboardMetric::ActiveDays => "active_days",
+            LeaderboardMetric::Projects => "projects",
+        }
+    }
+}
+
+impl From<LeaderboardMetric> for String {
+    fn from(metric: LeaderboardMetric) -> String {
+        metric.as_str().to_string()
+    }
+}
+
+impl TryFrom<String> for LeaderboardMetric {
+    type Error = String;
+
+    fn try_from(metric: String) -> Result<Self, Self::Error> {
+        serde_json::from_value(serde_json::Value::String(metric))
+            .map_err(|e| format!("Invalid leaderboard metric: {e}"))
+    }
+}
+
 #[derive(Queryable, Clone, Debug, Serialize, Hash, Eq, PartialEq, Identifiable)]
 pub struct Leaderboard {
     pub id: i32,
     pub name: String,
     pub invite_code: String,
-    pub creation_time: chrono::NaiveDateTime,
+    pub creation_time: chrono::DateTime<chrono::Utc>,
+    #[diesel(deserialize_as = String)]
+    pub time_window: LeaderboardWindow,
+    pub window_days: Option<i32>,
+    #[diesel(deserialize_as = String)]
+    pub metric: LeaderboardMetric,
+    pub languages: Option<Vec<String>>,
+    pub project_pattern: Option<String>,
+    pub editor: Option<String>,
+    pub public: bool,
+    /// Seasons that ended before this are not recorded
+    pub seasons_since: chrono::DateTime<chrono::Utc>,
+    pub description: Option<String>,
+    pub avatar_url: Option<String>,
+    pub owner_id: Option<i32>,
+    pub approval_required: bool,
+    pub exclude_manual: bool,
+}
+
+impl Leaderboard {
+    pub fn config(&self) -> LeaderboardConfig {
+        LeaderboardConfig {
+            time_window: self.time_window,
+            window_days: self.window_days,
+            metric: self.metric,
+            languages: self.languages.clone(),
+            project_pattern: self.project_pattern.clone(),
+            editor: self.editor.clone(),
+            public: self.public,
+            description: self.description.clone(),
+            avatar_url: self.avatar_url.clone(),
+            approval_required: self.approval_required,
+            exclude_manual: self.exclude_manual,
+        }
+    }
 }
 
 use crate::schema::leaderboards;
@@ -178,7 +775,46 @@ use crate::schema::leaderboards;
 pub struct NewLeaderboard {
     pub name: String,
     pub invite_code: String,
-    pub creation_time: chrono::NaiveDateTime,
+    pub creation_time: chrono::DateTime<chrono::Utc>,
+    #[diesel(serialize_as = String)]
+    pub time_window: LeaderboardWindow,
+    pub window_days: Option<i32>,
+    #[diesel(serialize_as = String)]
+    pub metric: LeaderboardMetric,
+    pub languages: Option<Vec<String>>,
+    pub project_pattern: Option<String>,
+    pub editor: Option<String>,
+    pub public: bool,
+    pub description: Option<String>,
+    pub avatar_url: Option<String>,
+    pub owner_id: Option<i32>,
+    pub approval_required: bool,
+    pub exclude_manual: bool,
+}
+
+/// Settings of a leaderboard that its admins can change
+#[derive(AsChangeset, Serialize, Deserialize, Clone, Debug, Default)]
+#[diesel(table_name = leaderboards, treat_none_as_null = true)]
+pub struct LeaderboardConfig {
+    #[diesel(serialize_as = String)]
+    pub time_window: LeaderboardWindow,
+    pub window_days: Option<i32>,
+    #[diesel(serialize_as = String)]
+    pub metric: LeaderboardMetric,
+    /// Only activities in these languages are counted
+    pub languages: Option<Vec<String>>,
+    /// Only activities with a project name matching this `LIKE` pattern are counted
+    pub project_pattern: Option<String>,
+    /// Only activities from this editor are counted
+    pub editor: Option<String>,
+    /// Whether the leaderboard can be found, viewed and joined without an invite
+    pub public: bool,
+    pub description: Option<String>,
+    pub avatar_url: Option<String>,
+    /// Whether joining the leaderboard has to be approved by an admin
+    pub approval_required: bool,
+    /// Whether manually entered activities are left out of the ranking
+    pub exclude_manual: bool,
 }
 
 #[derive(Queryable, Clone, Debug, Identifiable, Associations)]
@@ -192,6 +828,77 @@ pub struct LeaderboardMember {
     pub admin: bool,
 }
 
+use crate::schema::leaderboard_invites;
+
+/// An additional invite to a leaderboard that can expire or be used up
+#[derive(Queryable, Identifiable, Associations, Serialize, Deserialize, Clone, Debug)]
+#[diesel(belongs_to(Leaderboard), table_name = leaderboard_invites)]
+pub struct InviteLink {
+    #[serde(skip)]
+    pub id: i32,
+    #[serde(skip)]
+    pub leaderboard_id: i32,
+    pub name: String,
+    pub invite_code: String,
+    #[serde(skip)]
+    pub created_by: Option<i32>,
+    pub creation_time: chrono::DateTime<chrono::Utc>,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub max_uses: Option<i32>,
+    pub uses: i32,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = leaderboard_invites)]
+pub struct NewInviteLink {
+    pub leaderboard_id: i32,
+    pub name: String,
+    pub invite_code: String,
+    pub created_by: Option<i32>,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub max_uses: Option<i32>,
+}
+
+/// The outcome of trying to join a leaderboard
+pub enum LeaderboardJoin {
+    Joined(crate::api::users::MinimalLeaderboard),
+    /// The leaderboard requires approval, a join request was created
+    Requested(String),
+}
+
+use crate::schema::leaderboard_join_requests;
+
+#[derive(Insertable)]
+#[diesel(table_name = leaderboard_join_requests)]
+pub struct NewJoinRequest {
+    pub leaderboard_id: i32,
+    pub user_id: i32,
+    /// The invite link used, its use is counted when the request is approved
+    pub invite_id: Option<i32>,
+}
+
+#[derive(Queryable, Serialize, Deserialize, Clone, Debug)]
+pub struct JoinRequest {
+    pub username: String,
+    pub creation_time: chrono::DateTime<chrono::Utc>,
+}
+
+use crate::schema::leaderboard_bans;
+
+#[derive(Insertable)]
+#[diesel(table_name = leaderboard_bans)]
+pub struct NewLeaderboardBan {
+    pub leaderboard_id: i32,
+    pub user_id: i32,
+    pub banned_by: Option<i32>,
+}
+
+#[derive(Queryable, Serialize, Deserialize, Clone, Debug)]
+pub struct LeaderboardBan {
+    pub username: String,
+    pub creation_time: chrono::DateTime<chrono::Utc>,
+}
+
 use crate::schema::leaderboard_members;
 
 #[derive(Insertable)]
@@ -202,19 +909,89 @@ pub struct NewLeaderboardMember {
     pub admin: bool,
 }
 
-#[derive(Serialize, Clone, Debug, Deserialize)]
+#[derive(QueryableByName, Serialize, Clone, Debug, Deserialize)]
 pub struct PrivateLeaderboardMember {
+    #[diesel(sql_type = diesel::sql_types::Integer)]
     pub id: i32,
+    #[diesel(sql_type = diesel::sql_types::Text)]
     pub username: String,
+    #[diesel(sql_type = diesel::sql_types::Bool)]
     pub admin: bool,
+    #[diesel(sql_type = diesel::sql_types::Integer)]
     pub time_coded: i32,
+    /// Value of the metric of the leaderboard that the members are ranked by
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub score: i64,
+}
+
+use crate::schema::{leaderboard_season_rankings, leaderboard_seasons};
+
+#[derive(Queryable, Identifiable, Associations, Clone, Debug)]
+#[diesel(belongs_to(Leaderboard))]
+pub struct LeaderboardSeason {
+    pub id: i32,
+    pub leaderboard_id: i32,
+    pub period: String,
+    pub start_time: chrono::DateTime<chrono::Utc>,
+    pub end_time: chrono::DateTime<chrono::Utc>,
+    #[diesel(deserialize_as = String)]
+    pub metric: LeaderboardMetric,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = leaderboard_seasons)]
+pub struct NewLeaderboardSeason {
+    pub leaderboard_id: i32,
+    pub period: String,
+    pub start_time: chrono::DateTime<chrono::Utc>,
+    pub end_time: chrono::DateTime<chrono::Utc>,
+    #[diesel(serialize_as = String)]
+    pub metric: LeaderboardMetric,
+}
+
+/// The position of a member in the final ranking of a season
+#[derive(Queryable, Insertable, Serialize, Deserialize, Clone, Debug)]
+#[diesel(table_name = leaderboard_season_rankings)]
+pub struct SeasonRanking {
+    #[serde(skip)]
+    pub season_id: i32,
+    pub position: i32,
+    #[serde(skip)]
+    pub user_id: Option<i32>,
+    pub username: String,
+    pub time_coded: i32,
+    pub score: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SeasonSummary {
+    pub period: String,
+    pub start_time: chrono::DateTime<chrono::Utc>,
+    pub end_time: chrono::DateTime<chrono::Utc>,
+    pub metric: LeaderboardMetric,
+    pub winner: Option<SeasonRanking>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SeasonSnapshot {
+    pub period: String,
+    pub start_time: chrono::DateTime<chrono::Utc>,
+    pub end_time: chrono::DateTime<chrono::Utc>,
+    pub metric: LeaderboardMetric,
+    pub members: Vec<SeasonRanking>,
 }
 
 #[derive(Serialize, Clone, Debug, Deserialize)]
 pub struct PrivateLeaderboard {
     pub name: String,
-    pub invite: String,
-    pub creation_time: chrono::NaiveDateTime,
+    /// Only shown to the members of the leaderboard
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub invite: Option<String>,
+    pub creation_time: chrono::DateTime<chrono::Utc>,
+    /// Username of the owner, who is always an admin
+    pub owner: Option<String>,
+    #[serde(flatten)]
+    pub config: LeaderboardConfig,
     pub members: Vec<PrivateLeaderboardMember>,
 }
 
@@ -227,7 +1004,7 @@ pub struct CodingTimeSteps {
 
 #[derive(Serialize, Deserialize, Hash, Eq, PartialEq, Debug, Clone)]
 pub struct CurrentActivity {
-    pub started: chrono::NaiveDateTime,
+    pub started: chrono::DateTime<chrono::Utc>,
     pub duration: i64,
     pub heartbeat: HeartBeat,
 }
@@ -1,6 +1,13 @@
-use chrono::{serde::ts_seconds_option, DateTime, Utc};
+use std::{fmt, str::FromStr};
+
+use chrono::{
+    serde::{ts_seconds, ts_seconds_option},
+    DateTime, NaiveDate, Utc,
+};
 use serde::{Deserialize, Deserializer, Serialize};
 
+use crate::models::CodingActivity;
+
 #[derive(Deserialize, Serialize, Debug, Hash, Eq, PartialEq, Clone)]
 pub struct HeartBeat {
     #[serde(deserialize_with = "project_deserialize")]
@@ -10,21 +17,88 @@ pub struct HeartBeat {
     pub hostname: Option<String>,
 }
 
+#[derive(Deserialize, Debug)]
+pub struct TimestampedHeartBeat {
+    #[serde(flatten)]
+    pub heartbeat: HeartBeat,
+    #[serde(with = "ts_seconds")]
+    pub timestamp: DateTime<Utc>,
+}
+
+/// A heartbeat in the format sent by WakaTime plugins
+#[derive(Deserialize, Debug)]
+pub struct WakaTimeHeartBeat {
+    pub time: f64,
+    pub project: Option<String>,
+    pub language: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+/// A data dump exported from WakaTime
+#[derive(Deserialize, Debug)]
+pub struct WakaTimeExport {
+    pub days: Vec<WakaTimeDay>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct WakaTimeDay {
+    pub date: NaiveDate,
+    #[serde(default)]
+    pub projects: Vec<WakaTimeProject>,
+    #[serde(default)]
+    pub languages: Vec<WakaTimeDuration>,
+    #[serde(default)]
+    pub editors: Vec<WakaTimeDuration>,
+    #[serde(default)]
+    pub machines: Vec<WakaTimeDuration>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct WakaTimeProject {
+    pub name: String,
+    pub grand_total: Option<WakaTimeGrandTotal>,
+    #[serde(default)]
+    pub languages: Vec<WakaTimeDuration>,
+    #[serde(default)]
+    pub editors: Vec<WakaTimeDuration>,
+    #[serde(default)]
+    pub machines: Vec<WakaTimeDuration>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct WakaTimeGrandTotal {
+    pub total_seconds: f64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct WakaTimeDuration {
+    pub name: String,
+    pub total_seconds: f64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ImportRequest {
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
 fn project_deserialize<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
 where
     D: Deserializer<'de>,
 {
     let project = Option::<String>::deserialize(deserializer)?;
-    Ok(project.map(|p| {
-        if p.starts_with("tmp.") {
-            String::from("tmp")
-        } else {
-            p
-        }
-    }))
+    Ok(project.map(normalize_project_name))
 }
 
-#[derive(Deserialize, Debug)]
+pub fn normalize_project_name(project: String) -> String {
+    if project.starts_with("tmp.") {
+        String::from("tmp")
+    } else {
+        project
+    }
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
 pub struct DataRequest {
     #[serde(default)]
     #[serde(with = "ts_seconds_option")]
@@ -37,6 +111,210 @@ pub struct DataRequest {
     pub language: Option<String>,
     pub hostname: Option<String>,
     pub project_name: Option<String>,
+    pub limit: Option<i64>,
+    pub order: Option<SortOrder>,
+    #[serde(default)]
+    #[serde(deserialize_with = "cursor_deserialize")]
+    pub cursor: Option<ActivityCursor>,
+}
+
+impl DataRequest {
+    /// Whether the request filters the activities, pagination is not counted
+    pub fn has_filter(&self) -> bool {
+        self.from.is_some()
+            || self.to.is_some()
+            || self.min_duration.is_some()
+            || self.editor_name.is_some()
+            || self.language.is_some()
+            || self.hostname.is_some()
+            || self.project_name.is_some()
+    }
+}
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Position of an activity in a listing ordered by start time and id
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ActivityCursor {
+    pub start_time: DateTime<Utc>,
+    pub id: i32,
+}
+
+impl From<&CodingActivity> for ActivityCursor {
+    fn from(activity: &CodingActivity) -> Self {
+        ActivityCursor {
+            start_time: activity.start_time,
+            id: activity.id,
+        }
+    }
+}
+
+impl fmt::Display for ActivityCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}_{}", self.start_time.timestamp_micros(), self.id)
+    }
+}
+
+impl FromStr for ActivityCursor {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start_time, id) = s.split_once('_').ok_or("Invalid cursor")?;
+
+        Ok(ActivityCursor {
+            start_time: start_time
+                .parse()
+                .ok()
+                .and_then(DateTime::from_timestamp_micros)
+                .ok_or("Invalid cursor")?,
+            id: id.parse().map_err(|_| "Invalid cursor")?,
+        })
+    }
+}
+
+fn cursor_deserialize<'de, D>(deserializer: D) -> Result<Option<ActivityCursor>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|cursor| cursor.parse().map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DataFormat {
+    Json,
+    Csv,
+    Ndjson,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DataFormatRequest {
+    pub format: Option<DataFormat>,
+    /// Whether JSON is returned as a page with the cursor of the next page
+    #[serde(default)]
+    pub paginate: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggregateField {
+    Project,
+    Language,
+    Editor,
+    Hostname,
+}
+
+impl AggregateField {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AggregateField::Project => "project",
+            AggregateField::Language => "language",
+            AggregateField::Editor => "editor",
+            AggregateField::Hostname => "hostname",
+        }
+    }
+}
+
+impl FromStr for AggregateField {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "project" => Ok(AggregateField::Project),
+            "language" => Ok(AggregateField::Language),
+            "editor" => Ok(AggregateField::Editor),
+            "hostname" => Ok(AggregateField::Hostname),
+            _ => Err(format!("Can't group by {s}")),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeBucket {
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl TimeBucket {
+    /// The unit passed to `date_trunc`
+    pub fn unit(&self) -> &'static str {
+        match self {
+            TimeBucket::Hour => "hour",
+            TimeBucket::Day => "day",
+            TimeBucket::Week => "week",
+            TimeBucket::Month => "month",
+            TimeBucket::Year => "year",
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct AggregateRequest {
+    #[serde(default)]
+    #[serde(with = "ts_seconds_option")]
+    pub from: Option<DateTime<Utc>>,
+    #[serde(default)]
+    #[serde(with = "ts_seconds_option")]
+    pub to: Option<DateTime<Utc>>,
+    #[serde(default)]
+    #[serde(deserialize_with = "group_by_deserialize")]
+    pub group_by: Vec<AggregateField>,
+    pub bucket: Option<TimeBucket>,
+}
+
+/// Parses a comma separated list of fields, e.g. `language,project`
+fn group_by_deserialize<'de, D>(deserializer: D) -> Result<Vec<AggregateField>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let mut fields = Vec::new();
+    for field in String::deserialize(deserializer)?
+        .split(',')
+        .map(str::trim)
+        .filter(|field| !field.is_empty())
+    {
+        let field = field.parse().map_err(serde::de::Error::custom)?;
+        if !fields.contains(&field) {
+            fields.push(field);
+        }
+    }
+
+    Ok(fields)
+}
+
+#[derive(Deserialize, Debug)]
+pub struct GoalsRequest {
+    pub daily_goal: Option<i32>,
+    pub weekly_goal: Option<i32>,
+}
+
+/// Missing fields keep their current value
+#[derive(Deserialize, Debug)]
+pub struct FriendVisibilityRequest {
+    pub hide_project_names: Option<bool>,
+    pub hide_hostnames: Option<bool>,
+    pub hide_editors: Option<bool>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BlockRequest {
+    pub username: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct StreakRequest {
+    pub min_minutes: Option<i64>,
 }
 
 #[derive(Deserialize, Debug)]
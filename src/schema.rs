@@ -4,12 +4,21 @@ diesel::table! {
     coding_activities (id) {
         id -> Int4,
         user_id -> Int4,
-        start_time -> Timestamp,
+        start_time -> Timestamptz,
         duration -> Int4,
         project_name -> Nullable<Varchar>,
         language -> Nullable<Varchar>,
         editor_name -> Nullable<Varchar>,
         hostname -> Nullable<Varchar>,
+        source -> Varchar,
+    }
+}
+
+diesel::table! {
+    coding_goals (user_id) {
+        user_id -> Int4,
+        daily_goal -> Nullable<Int4>,
+        weekly_goal -> Nullable<Int4>,
     }
 }
 
@@ -18,6 +27,53 @@ diesel::table! {
         id -> Int4,
         lesser_id -> Int4,
         greater_id -> Int4,
+        requester_id -> Nullable<Int4>,
+        accepted -> Bool,
+        creation_time -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    friend_visibility (user_id, friend_id) {
+        user_id -> Int4,
+        friend_id -> Int4,
+        hide_project_names -> Bool,
+        hide_hostnames -> Bool,
+        hide_editors -> Bool,
+    }
+}
+
+diesel::table! {
+    leaderboard_bans (id) {
+        id -> Int4,
+        leaderboard_id -> Int4,
+        user_id -> Int4,
+        banned_by -> Nullable<Int4>,
+        creation_time -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    leaderboard_invites (id) {
+        id -> Int4,
+        leaderboard_id -> Int4,
+        name -> Varchar,
+        invite_code -> Varchar,
+        created_by -> Nullable<Int4>,
+        creation_time -> Timestamptz,
+        expires_at -> Nullable<Timestamptz>,
+        max_uses -> Nullable<Int4>,
+        uses -> Int4,
+    }
+}
+
+diesel::table! {
+    leaderboard_join_requests (id) {
+        id -> Int4,
+        leaderboard_id -> Int4,
+        user_id -> Int4,
+        invite_id -> Nullable<Int4>,
+        creation_time -> Timestamptz,
     }
 }
 
@@ -30,12 +86,68 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    leaderboard_season_rankings (season_id, position) {
+        season_id -> Int4,
+        position -> Int4,
+        user_id -> Nullable<Int4>,
+        username -> Varchar,
+        time_coded -> Int4,
+        score -> Int8,
+    }
+}
+
+diesel::table! {
+    leaderboard_seasons (id) {
+        id -> Int4,
+        leaderboard_id -> Int4,
+        period -> Varchar,
+        start_time -> Timestamptz,
+        end_time -> Timestamptz,
+        metric -> Varchar,
+    }
+}
+
 diesel::table! {
     leaderboards (id) {
         id -> Int4,
         name -> Varchar,
         invite_code -> Varchar,
-        creation_time -> Timestamp,
+        creation_time -> Timestamptz,
+        time_window -> Varchar,
+        window_days -> Nullable<Int4>,
+        metric -> Varchar,
+        languages -> Nullable<Array<Text>>,
+        project_pattern -> Nullable<Varchar>,
+        editor -> Nullable<Varchar>,
+        public -> Bool,
+        seasons_since -> Timestamptz,
+        description -> Nullable<Varchar>,
+        avatar_url -> Nullable<Varchar>,
+        owner_id -> Nullable<Int4>,
+        approval_required -> Bool,
+        exclude_manual -> Bool,
+    }
+}
+
+diesel::table! {
+    privacy_settings (user_id) {
+        user_id -> Int4,
+        hide_project_names -> Bool,
+        hide_hostnames -> Bool,
+        hide_editors -> Bool,
+        private_projects -> Array<Text>,
+    }
+}
+
+diesel::table! {
+    project_aliases (id) {
+        id -> Int4,
+        user_id -> Int4,
+        pattern -> Varchar,
+        match_kind -> Varchar,
+        target -> Varchar,
+        creation_time -> Timestamptz,
     }
 }
 
@@ -57,6 +169,15 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    user_blocks (id) {
+        id -> Int4,
+        blocker_id -> Int4,
+        blocked_id -> Int4,
+        creation_time -> Timestamptz,
+    }
+}
+
 diesel::table! {
     user_identities (id) {
         id -> Int4,
@@ -65,21 +186,44 @@ diesel::table! {
         username -> Varchar,
         registration_time -> Timestamp,
         is_public -> Bool,
+        timezone -> Varchar,
     }
 }
 
 diesel::joinable!(coding_activities -> user_identities (user_id));
+diesel::joinable!(coding_goals -> user_identities (user_id));
+diesel::joinable!(leaderboard_bans -> leaderboards (leaderboard_id));
+diesel::joinable!(leaderboard_invites -> leaderboards (leaderboard_id));
+diesel::joinable!(leaderboard_invites -> user_identities (created_by));
+diesel::joinable!(leaderboard_join_requests -> leaderboards (leaderboard_id));
+diesel::joinable!(leaderboard_join_requests -> user_identities (user_id));
 diesel::joinable!(leaderboard_members -> leaderboards (leaderboard_id));
 diesel::joinable!(leaderboard_members -> user_identities (user_id));
+diesel::joinable!(leaderboard_season_rankings -> leaderboard_seasons (season_id));
+diesel::joinable!(leaderboard_season_rankings -> user_identities (user_id));
+diesel::joinable!(leaderboard_seasons -> leaderboards (leaderboard_id));
+diesel::joinable!(leaderboards -> user_identities (owner_id));
+diesel::joinable!(privacy_settings -> user_identities (user_id));
+diesel::joinable!(project_aliases -> user_identities (user_id));
 diesel::joinable!(testausid_users -> user_identities (identity));
 diesel::joinable!(testaustime_users -> user_identities (identity));
 
 diesel::allow_tables_to_appear_in_same_query!(
     coding_activities,
+    coding_goals,
     friend_relations,
+    friend_visibility,
+    leaderboard_bans,
+    leaderboard_invites,
+    leaderboard_join_requests,
     leaderboard_members,
+    leaderboard_season_rankings,
+    leaderboard_seasons,
     leaderboards,
+    privacy_settings,
+    project_aliases,
     testausid_users,
     testaustime_users,
+    user_blocks,
     user_identities,
 );
@@ -64,4 +64,345 @@ async fn public_accounts() {
     assert!(resp.status().is_success(), "Failed to delete user");
 }
 
+#[actix_web::test]
+async fn exporting_data_works() {
+    let app = test::init_service(App::new().configure(init_test_services)).await;
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 80u16);
+    let other_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)), 80u16);
+    let blocked_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 3)), 80u16);
+
+    let body = json!({"username": "exporter", "password": "password"});
+    let resp = request!(app, addr, post, "/auth/register", body);
+    let user: NewUserIdentity = test::read_body_json(resp).await;
+
+    let friend_body = json!({"username": "exporterfriend", "password": "password"});
+    let resp = request!(app, other_addr, post, "/auth/register", friend_body);
+    let friend: NewUserIdentity = test::read_body_json(resp).await;
+
+    let blocked_body = json!({"username": "exporterblocked", "password": "password"});
+    let resp = request!(app, blocked_addr, post, "/auth/register", blocked_body);
+    assert!(resp.status().is_success(), "Registration failed");
+
+    let resp = TestRequest::post()
+        .peer_addr(addr)
+        .uri("/friends/add")
+        .insert_header(("authorization", "Bearer ".to_owned() + &user.auth_token))
+        .set_payload(friend.friend_code.clone())
+        .send_request(&app)
+        .await;
+    assert!(resp.status().is_success(), "Sending friend request failed");
+
+    let resp = TestRequest::post()
+        .peer_addr(other_addr)
+        .uri("/friends/requests/accept")
+        .insert_header(("authorization", "Bearer ".to_owned() + &friend.auth_token))
+        .set_payload("exporter")
+        .send_request(&app)
+        .await;
+    assert!(
+        resp.status().is_success(),
+        "Accepting friend request failed"
+    );
+
+    let create = json!({"name": "exportboard"});
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/create",
+        user.auth_token,
+        create
+    );
+    assert!(resp.status().is_success(), "Leaderboard creation failed");
+
+    let heartbeat = json!({
+        "project_name": "cool project",
+        "language": "rust",
+        "editor_name": "nvim",
+        "hostname": "hostname",
+    });
+    for _ in 0..2 {
+        let resp = request_auth!(
+            app,
+            addr,
+            post,
+            "/activity/update",
+            user.auth_token,
+            heartbeat
+        );
+        assert!(resp.status().is_success(), "Sending heartbeat failed");
+        let resp = request_auth!(app, addr, post, "/activity/flush", user.auth_token);
+        assert!(resp.status().is_success(), "Flushing failed");
+    }
+
+    let resp = request_auth!(app, addr, get, "/users/@me/export", user.auth_token);
+    assert!(
+        resp.status().is_client_error(),
+        "Exporting should require secured access"
+    );
+
+    let resp = request!(app, addr, post, "/auth/securedaccess", body);
+    let sat: SecuredAccessTokenResponse = test::read_body_json(resp).await;
+
+    let settings = [
+        (
+            "/account/settings",
+            &sat.token,
+            json!({"timezone": "Europe/Helsinki"}),
+        ),
+        ("/users/@me/goals", &sat.token, json!({"daily_goal": 3600})),
+        (
+            "/account/privacy",
+            &sat.token,
+            json!({"hide_hostnames": true, "private_projects": ["secret"]}),
+        ),
+        (
+            "/activity/aliases",
+            &user.auth_token,
+            json!({"pattern": "old name", "target": "new name"}),
+        ),
+        (
+            "/friends/exporterfriend/visibility",
+            &user.auth_token,
+            json!({"hide_editors": true}),
+        ),
+        (
+            "/users/@me/blocks",
+            &user.auth_token,
+            json!({"username": "exporterblocked"}),
+        ),
+    ];
+    for (uri, token, change) in settings {
+        let resp = request_auth!(app, addr, post, uri, token, change);
+        assert!(resp.status().is_success(), "Changing {uri} failed");
+    }
+
+    let resp = request_auth!(app, addr, get, "/users/@me/export", sat.token);
+    assert!(resp.status().is_success(), "Exporting failed");
+
+    let export: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(export["user"]["username"], "exporter");
+    assert!(
+        export["user"].get("auth_token").is_none(),
+        "Secrets should not be exported"
+    );
+    assert_eq!(export["user"]["timezone"], "Europe/Helsinki");
+    assert_eq!(
+        export["goals"],
+        json!({ "daily_goal": 3600, "weekly_goal": null })
+    );
+    assert_eq!(export["privacy"]["hide_hostnames"], true);
+    assert_eq!(export["privacy"]["private_projects"], json!(["secret"]));
+    assert_eq!(export["project_aliases"][0]["pattern"], "old name");
+    assert_eq!(export["project_aliases"][0]["target"], "new name");
+    assert_eq!(export["friends"], json!(["exporterfriend"]));
+    assert_eq!(
+        export["friend_visibility"],
+        json!([{
+            "friend": "exporterfriend",
+            "hide_project_names": false,
+            "hide_hostnames": false,
+            "hide_editors": true,
+        }])
+    );
+    assert_eq!(export["blocked_users"][0]["username"], "exporterblocked");
+    assert_eq!(
+        export["leaderboards"],
+        json!([{ "name": "exportboard", "admin": true }])
+    );
+    assert_eq!(
+        export["activities"].as_array().unwrap().len(),
+        2,
+        "All activities should be exported"
+    );
+
+    let resp = request_auth!(app, addr, delete, "/leaderboards/exportboard", sat.token);
+    assert!(resp.status().is_success(), "Leaderboard deletion failed");
+
+    let resp = request!(app, addr, delete, "/users/@me/delete", body);
+    assert!(resp.status().is_success(), "Failed to delete user");
+
+    let resp = request!(app, addr, delete, "/users/@me/delete", friend_body);
+    assert!(resp.status().is_success(), "Failed to delete user");
+
+    let resp = request!(app, addr, delete, "/users/@me/delete", blocked_body);
+    assert!(resp.status().is_success(), "Failed to delete user");
+}
+
 // TODO: add test for searching public accounts
+
+#[actix_web::test]
+async fn timezones_work() {
+    let app = test::init_service(App::new().configure(init_test_services)).await;
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 80u16);
+
+    let body = json!({"username": "traveller", "password": "password"});
+    let resp = request!(app, addr, post, "/auth/register", body);
+    let user: NewUserIdentity = test::read_body_json(resp).await;
+
+    let resp = request!(app, addr, post, "/auth/securedaccess", body);
+    let sat: SecuredAccessTokenResponse = test::read_body_json(resp).await;
+
+    let change = json!({"timezone": "Mars/Olympus_Mons"});
+    let resp = request_auth!(app, addr, post, "/account/settings", sat.token, change);
+    assert_eq!(resp.status(), 400, "Unknown timezones should be rejected");
+
+    let change = json!({"timezone": "America/New_York", "public_profile": true});
+    let resp = request_auth!(app, addr, post, "/account/settings", sat.token, change);
+    assert!(resp.status().is_success(), "Changing timezone failed");
+
+    let resp = request_auth!(app, addr, get, "/users/@me", user.auth_token);
+    let profile: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(profile["timezone"], "America/New_York");
+
+    // Half an hour after midnight UTC is still the previous day in New York
+    let day = chrono::Utc::now().date_naive() - chrono::Duration::days(2);
+    let start = day.and_hms_opt(0, 30, 0).unwrap().and_utc().timestamp();
+    let batch = json!([
+        {"timestamp": start, "project_name": "project", "language": "rust"},
+        {"timestamp": start + 60, "project_name": "project", "language": "rust"},
+    ]);
+    let resp = request_auth!(app, addr, post, "/activity/batch", user.auth_token, batch);
+    assert!(resp.status().is_success(), "Sending a batch should succeed");
+
+    let resp = request_auth!(
+        app,
+        addr,
+        get,
+        "/users/@me/activity/aggregate?bucket=day",
+        user.auth_token
+    );
+    let aggregate: serde_json::Value = test::read_body_json(resp).await;
+    let bucket = aggregate[0]["bucket"]
+        .as_str()
+        .unwrap()
+        .parse::<chrono::DateTime<chrono::Utc>>()
+        .unwrap()
+        .with_timezone(&chrono_tz::America::New_York);
+    assert_eq!(
+        bucket.naive_local(),
+        (day - chrono::Duration::days(1))
+            .and_hms_opt(0, 0, 0)
+            .unwrap(),
+        "Days should start at midnight in the timezone of the user"
+    );
+
+    let resp = request!(
+        app,
+        addr,
+        get,
+        "/users/traveller/activity/aggregate?bucket=day"
+    );
+    let public_aggregate: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(
+        public_aggregate[0]["bucket"], aggregate[0]["bucket"],
+        "Other users should see the days in the timezone of the user"
+    );
+
+    let resp = request!(app, addr, delete, "/users/@me/delete", body);
+    assert!(resp.status().is_success(), "Failed to delete user");
+}
+
+#[actix_web::test]
+async fn privacy_settings_work() {
+    let app = test::init_service(App::new().configure(init_test_services)).await;
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 80u16);
+
+    let body = json!({"username": "consultant", "password": "password"});
+    let resp = request!(app, addr, post, "/auth/register", body);
+    let user: NewUserIdentity = test::read_body_json(resp).await;
+
+    for project in ["secret client", "open source"] {
+        let heartbeat = json!({
+            "project_name": project,
+            "language": "rust",
+            "editor_name": "nvim",
+            "hostname": "work laptop",
+        });
+        let resp = request_auth!(
+            app,
+            addr,
+            post,
+            "/activity/update",
+            user.auth_token,
+            heartbeat
+        );
+        assert!(resp.status().is_success(), "Sending heartbeat failed");
+        let resp = request_auth!(app, addr, post, "/activity/flush", user.auth_token);
+        assert!(resp.status().is_success(), "Flushing failed");
+    }
+
+    let privacy = json!({"hide_hostnames": true, "private_projects": ["Secret Client"]});
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/account/privacy",
+        user.auth_token,
+        privacy
+    );
+    assert!(
+        resp.status().is_client_error(),
+        "Changing privacy settings should require secured access"
+    );
+
+    let resp = request!(app, addr, post, "/auth/securedaccess", body);
+    let sat: SecuredAccessTokenResponse = test::read_body_json(resp).await;
+
+    let resp = request_auth!(app, addr, post, "/account/privacy", sat.token, privacy);
+    assert!(
+        resp.status().is_success(),
+        "Changing privacy settings failed"
+    );
+
+    let change = json!({"public_profile": true});
+    let resp = request_auth!(app, addr, post, "/account/settings", sat.token, change);
+    assert!(resp.status().is_success(), "Changing settings failed");
+
+    let resp = request!(app, addr, get, "/users/consultant/activity/data");
+    let activities: Vec<serde_json::Value> = test::read_body_json(resp).await;
+    assert_eq!(activities.len(), 2);
+    assert_eq!(activities[0]["project_name"], "private project");
+    assert_eq!(activities[1]["project_name"], "open source");
+    assert_eq!(activities[1]["hostname"], "private host");
+    assert_eq!(activities[1]["editor_name"], "nvim");
+
+    let resp = request!(
+        app,
+        addr,
+        get,
+        "/users/consultant/activity/data?project_name=secret%20client"
+    );
+    assert!(
+        resp.status().is_client_error(),
+        "Private projects can't be filtered by"
+    );
+
+    let resp = request!(
+        app,
+        addr,
+        get,
+        "/users/consultant/activity/aggregate?group_by=project,hostname"
+    );
+    let aggregate: Vec<serde_json::Value> = test::read_body_json(resp).await;
+    assert_eq!(aggregate.len(), 2);
+    assert!(aggregate
+        .iter()
+        .all(|entry| entry["hostname"] == "private host"));
+
+    let resp = request_auth!(app, addr, get, "/users/@me/activity/data", user.auth_token);
+    let activities: Vec<serde_json::Value> = test::read_body_json(resp).await;
+    assert_eq!(
+        activities[0]["project_name"], "secret client",
+        "The owner sees everything"
+    );
+    assert_eq!(activities[0]["hostname"], "work laptop");
+
+    let resp = request_auth!(app, addr, get, "/account/privacy", user.auth_token);
+    let privacy: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(privacy["private_projects"], json!(["Secret Client"]));
+    assert_eq!(privacy["hide_project_names"], false);
+
+    let resp = request!(app, addr, delete, "/users/@me/delete", body);
+    assert!(resp.status().is_success(), "Failed to delete user");
+}
@@ -5,7 +5,9 @@ use serde_json::json;
 
 use super::{macros::*, *};
 use crate::{
-    models::{CurrentActivity, NewUserIdentity},
+    api::activity::{flush_all_heartbeats, flush_idle_heartbeats, HeartBeatMemoryStore},
+    database::DatabaseWrapper,
+    models::{CurrentActivity, NewUserIdentity, SecuredAccessTokenResponse},
     requests::HeartBeat,
 };
 
@@ -124,6 +126,66 @@ async fn updating_activity_works() {
     assert!(resp.status().is_success(), "Failed to delete user");
 }
 
+#[actix_web::test]
+async fn concurrent_updates_finish_a_session_once() {
+    let app = test::init_service(App::new().configure(init_test_services)).await;
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 80u16);
+
+    let body = json!({"username": "concurrentuser", "password": "password"});
+    let resp = request!(app, addr, post, "/auth/register", body);
+    let user: NewUserIdentity = test::read_body_json(resp).await;
+
+    let first = json!({"project_name": "first project"});
+    let resp = request_auth!(app, addr, post, "/activity/update", user.auth_token, first);
+    assert!(
+        resp.status().is_success(),
+        "Sending heartbeat should succeed"
+    );
+
+    actix_web::rt::time::sleep(std::time::Duration::from_secs(1)).await;
+
+    let second = json!({"project_name": "second project"});
+    let updates = (0..10).map(|_| {
+        TestRequest::post()
+            .peer_addr(addr)
+            .uri("/activity/update")
+            .set_json(&second)
+            .insert_header(("authorization", "Bearer ".to_owned() + &user.auth_token))
+            .send_request(&app)
+    });
+    for resp in futures::future::join_all(updates).await {
+        assert!(
+            resp.status().is_success(),
+            "Sending heartbeat should succeed"
+        );
+    }
+
+    let resp = request_auth!(app, addr, get, "/users/@me/activity/data", user.auth_token);
+    let data: Vec<serde_json::Value> = test::read_body_json(resp).await;
+    assert_eq!(
+        data.len(),
+        1,
+        "The first session should be finished exactly once"
+    );
+
+    let resp = request_auth!(
+        app,
+        addr,
+        get,
+        "/users/@me/activity/current",
+        user.auth_token
+    );
+    let current: CurrentActivity = test::read_body_json(resp).await;
+    assert_eq!(
+        current.heartbeat.project_name.as_deref(),
+        Some("second project"),
+        "The second session should be current"
+    );
+
+    let resp = request!(app, addr, delete, "/users/@me/delete", body);
+    assert!(resp.status().is_success(), "Failed to delete user");
+}
+
 #[actix_web::test]
 async fn flushing_works() {
     let app = test::init_service(App::new().configure(init_test_services)).await;
@@ -170,4 +232,975 @@ async fn flushing_works() {
     assert!(resp.status().is_success(), "Failed to delete user");
 }
 
+#[actix_web::test]
+async fn flushing_all_sessions_works() {
+    let app = test::init_service(App::new().configure(init_test_services)).await;
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 80u16);
+
+    let body = json!({"username": "activeuser3", "password": "password"});
+    let resp = request!(app, addr, post, "/auth/register", body);
+    let user: NewUserIdentity = test::read_body_json(resp).await;
+
+    let resp = request_auth!(app, addr, get, "/users/@me", user.auth_token);
+    let profile: serde_json::Value = test::read_body_json(resp).await;
+    let user_id = profile["id"].as_i64().unwrap() as i32;
+
+    let heartbeat = HeartBeat {
+        hostname: Some(String::from("hostname")),
+        project_name: Some(String::from("cool project")),
+        language: Some(String::from("rust")),
+        editor_name: Some(String::from("nvim")),
+    };
+
+    let heartbeats = HeartBeatMemoryStore::new();
+    heartbeats.insert(
+        user_id,
+        (heartbeat, chrono::Utc::now(), chrono::Duration::seconds(60)),
+    );
+
+    let db = DatabaseWrapper::from(Data::new(Database::new(
+        std::env::var("TEST_DATABASE").unwrap(),
+    )));
+    flush_all_heartbeats(&db, &heartbeats).await;
+
+    assert!(
+        heartbeats.is_empty(),
+        "Store should be empty after flushing"
+    );
+
+    let resp = request_auth!(app, addr, get, "/users/@me/activity/data", user.auth_token);
+    let data: Vec<serde_json::Value> = test::read_body_json(resp).await;
+
+    assert_eq!(data.len(), 1, "Session should be saved after flushing");
+    assert_eq!(data[0]["duration"], 60, "Session duration should be kept");
+
+    let resp = request!(app, addr, delete, "/users/@me/delete", body);
+    assert!(resp.status().is_success(), "Failed to delete user");
+}
+
+#[actix_web::test]
+async fn flushing_idle_sessions_works() {
+    let app = test::init_service(App::new().configure(init_test_services)).await;
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 80u16);
+
+    let body = json!({"username": "idleuser", "password": "password"});
+    let resp = request!(app, addr, post, "/auth/register", body);
+    let user: NewUserIdentity = test::read_body_json(resp).await;
+
+    let resp = request_auth!(app, addr, get, "/users/@me", user.auth_token);
+    let profile: serde_json::Value = test::read_body_json(resp).await;
+    let user_id = profile["id"].as_i64().unwrap() as i32;
+
+    let heartbeat = HeartBeat {
+        hostname: Some(String::from("hostname")),
+        project_name: Some(String::from("cool project")),
+        language: Some(String::from("rust")),
+        editor_name: Some(String::from("nvim")),
+    };
+
+    let db = DatabaseWrapper::from(Data::new(Database::new(
+        std::env::var("TEST_DATABASE").unwrap(),
+    )));
+    let heartbeats = HeartBeatMemoryStore::new();
+    heartbeats.insert(
+        user_id,
+        (
+            heartbeat.clone(),
+            chrono::Utc::now(),
+            chrono::Duration::seconds(0),
+        ),
+    );
+
+    flush_idle_heartbeats(&db, &heartbeats, chrono::Duration::seconds(900)).await;
+    assert!(
+        heartbeats.contains_key(&user_id),
+        "Active sessions should not be flushed"
+    );
+
+    heartbeats.insert(
+        user_id,
+        (
+            heartbeat,
+            chrono::Utc::now() - chrono::Duration::seconds(1200),
+            chrono::Duration::seconds(60),
+        ),
+    );
+
+    flush_idle_heartbeats(&db, &heartbeats, chrono::Duration::seconds(900)).await;
+    assert!(
+        !heartbeats.contains_key(&user_id),
+        "Idle sessions should be flushed"
+    );
+
+    let resp = request_auth!(app, addr, get, "/users/@me/activity/data", user.auth_token);
+    let data: Vec<serde_json::Value> = test::read_body_json(resp).await;
+
+    assert_eq!(data.len(), 1, "Idle session should be saved");
+
+    let resp = request!(app, addr, delete, "/users/@me/delete", body);
+    assert!(resp.status().is_success(), "Failed to delete user");
+}
+
+#[actix_web::test]
+async fn batch_updating_works() {
+    let app = test::init_service(App::new().configure(init_test_services)).await;
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 80u16);
+
+    let body = json!({"username": "offlineuser", "password": "password"});
+    let resp = request!(app, addr, post, "/auth/register", body);
+    let user: NewUserIdentity = test::read_body_json(resp).await;
+
+    let start = chrono::Utc::now().timestamp() - 3600;
+    let batch = json!([
+        {
+            "timestamp": start + 120,
+            "project_name": "another project",
+            "language": "rust",
+            "editor_name": "nvim",
+            "hostname": "hostname",
+        },
+        {
+            "timestamp": start,
+            "project_name": "cool project",
+            "language": "rust",
+            "editor_name": "nvim",
+            "hostname": "hostname",
+        },
+        {
+            "timestamp": start + 60,
+            "project_name": "cool project",
+            "language": "rust",
+            "editor_name": "nvim",
+            "hostname": "hostname",
+        },
+        {
+            "timestamp": start + 150,
+            "project_name": "another project",
+            "language": "rust",
+            "editor_name": "nvim",
+            "hostname": "hostname",
+        },
+        {
+            "timestamp": start + 90,
+            "project_name": "a".repeat(65),
+            "language": "rust",
+            "editor_name": "nvim",
+            "hostname": "hostname",
+        },
+    ]);
+
+    let resp = request_auth!(app, addr, post, "/activity/batch", user.auth_token, batch);
+    assert!(resp.status().is_success(), "Sending a batch should succeed");
+
+    let result: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(result["sessions"], 2, "Heartbeats should be merged");
+    assert_eq!(result["skipped_sessions"], 0);
+    assert_eq!(
+        result["errors"][0]["index"], 4,
+        "Invalid heartbeats should be reported"
+    );
+
+    let resp = request_auth!(app, addr, post, "/activity/batch", user.auth_token, batch);
+    let result: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(
+        result["sessions"], 0,
+        "Sessions that were already stored should be skipped"
+    );
+    assert_eq!(
+        result["skipped_sessions"], 2,
+        "Skipped sessions should be reported"
+    );
+
+    let resp = request_auth!(app, addr, get, "/users/@me/activity/data", user.auth_token);
+    let data: Vec<serde_json::Value> = test::read_body_json(resp).await;
+
+    let session = data
+        .iter()
+        .find(|a| a["project_name"] == "cool project")
+        .expect("Merged session should be stored");
+    assert_eq!(session["duration"], 60, "Timestamps should be used");
+    assert_eq!(data.len(), 2, "Sessions should not be stored twice");
+
+    let resp = request!(app, addr, delete, "/users/@me/delete", body);
+    assert!(resp.status().is_success(), "Failed to delete user");
+}
+
+#[actix_web::test]
+async fn concurrent_batches_store_sessions_once() {
+    let app = test::init_service(App::new().configure(init_test_services)).await;
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 80u16);
+
+    let body = json!({"username": "concurrentbatcher", "password": "password"});
+    let resp = request!(app, addr, post, "/auth/register", body);
+    let user: NewUserIdentity = test::read_body_json(resp).await;
+
+    let start = chrono::Utc::now().timestamp() - 3600;
+    let batch = json!([
+        {"timestamp": start, "project_name": "project"},
+        {"timestamp": start + 60, "project_name": "project"},
+    ]);
+    let batches = (0..5).map(|_| {
+        TestRequest::post()
+            .peer_addr(addr)
+            .uri("/activity/batch")
+            .set_json(&batch)
+            .insert_header(("authorization", "Bearer ".to_owned() + &user.auth_token))
+            .send_request(&app)
+    });
+
+    let (mut sessions, mut skipped) = (0, 0);
+    for resp in futures::future::join_all(batches).await {
+        assert!(resp.status().is_success(), "Sending a batch should succeed");
+        let result: serde_json::Value = test::read_body_json(resp).await;
+        sessions += result["sessions"].as_i64().unwrap();
+        skipped += result["skipped_sessions"].as_i64().unwrap();
+    }
+    assert_eq!(sessions, 1, "The session should be stored exactly once");
+    assert_eq!(skipped, 4, "The other batches should report it as skipped");
+
+    let resp = request_auth!(app, addr, get, "/users/@me/activity/data", user.auth_token);
+    let data: Vec<serde_json::Value> = test::read_body_json(resp).await;
+    assert_eq!(data.len(), 1, "Sessions should not be stored twice");
+
+    let resp = request!(app, addr, delete, "/users/@me/delete", body);
+    assert!(resp.status().is_success(), "Failed to delete user");
+}
+
+#[actix_web::test]
+async fn activity_data_formats_work() {
+    let app = test::init_service(App::new().configure(init_test_services)).await;
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 80u16);
+
+    let body = json!({"username": "formatuser", "password": "password"});
+    let resp = request!(app, addr, post, "/auth/register", body);
+    let user: NewUserIdentity = test::read_body_json(resp).await;
+
+    let heartbeat = HeartBeat {
+        hostname: Some(String::from("hostname")),
+        project_name: Some(String::from("cool, \"quoted\" project")),
+        language: Some(String::from("rust")),
+        editor_name: None,
+    };
+
+    for _ in 0..2 {
+        let resp = request_auth!(
+            app,
+            addr,
+            post,
+            "/activity/update",
+            user.auth_token,
+            heartbeat
+        );
+        assert!(resp.status().is_success(), "Sending heartbeat failed");
+        let resp = request_auth!(app, addr, post, "/activity/flush", user.auth_token);
+        assert!(resp.status().is_success(), "Flushing failed");
+    }
+
+    let resp = request_auth!(
+        app,
+        addr,
+        get,
+        "/users/@me/activity/data?format=csv",
+        user.auth_token
+    );
+    assert!(resp.status().is_success(), "Getting CSV data failed");
+
+    let csv = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    let lines = csv.lines().collect::<Vec<_>>();
+    assert_eq!(
+        lines[0], "id,start_time,duration,project_name,language,editor_name,hostname,source",
+        "CSV should have a header"
+    );
+    assert_eq!(lines.len(), 3, "CSV should have a row for each activity");
+    assert!(
+        lines[1].ends_with(",\"cool, \"\"quoted\"\" project\",rust,,hostname,editor"),
+        "CSV fields should be escaped"
+    );
+
+    let resp = TestRequest::get()
+        .peer_addr(addr)
+        .uri("/users/@me/activity/data")
+        .insert_header(("authorization", "Bearer ".to_owned() + &user.auth_token))
+        .insert_header(("accept", "application/x-ndjson"))
+        .send_request(&app)
+        .await;
+    assert!(resp.status().is_success(), "Getting NDJSON data failed");
+
+    let ndjson = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    let activities = ndjson
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        activities.len(),
+        2,
+        "NDJSON should have a line per activity"
+    );
+    assert_eq!(activities[0]["project_name"], "cool, \"quoted\" project");
+
+    let resp = request!(app, addr, delete, "/users/@me/delete", body);
+    assert!(resp.status().is_success(), "Failed to delete user");
+}
+
+#[actix_web::test]
+async fn activity_data_pagination_works() {
+    let app = test::init_service(App::new().configure(init_test_services)).await;
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 80u16);
+
+    let body = json!({"username": "paginationuser", "password": "password"});
+    let resp = request!(app, addr, post, "/auth/register", body);
+    let user: NewUserIdentity = test::read_body_json(resp).await;
+
+    let start = chrono::Utc::now().timestamp() - 3600;
+    let batch = (0..6)
+        .map(|i| {
+            json!({
+                "timestamp": start + i * 30,
+                "project_name": format!("project {}", i / 2),
+                "language": "rust",
+                "editor_name": "nvim",
+                "hostname": "hostname",
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let resp = request_auth!(app, addr, post, "/activity/batch", user.auth_token, batch);
+    assert!(resp.status().is_success(), "Sending a batch should succeed");
+
+    let resp = request_auth!(
+        app,
+        addr,
+        get,
+        "/users/@me/activity/data?paginate=true&limit=2",
+        user.auth_token
+    );
+    assert!(resp.status().is_success(), "Getting first page failed");
+
+    let page: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(page["data"].as_array().unwrap().len(), 2);
+    assert_eq!(page["data"][0]["project_name"], "project 0");
+    let cursor = page["next_cursor"].as_str().unwrap().to_string();
+
+    let resp = request_auth!(
+        app,
+        addr,
+        get,
+        &format!("/users/@me/activity/data?paginate=true&cursor={cursor}"),
+        user.auth_token
+    );
+    let page: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(
+        page["data"].as_array().map(Vec::len),
+        Some(1),
+        "Pages without a limit should have up to 1000 activities"
+    );
+
+    let resp = request_auth!(
+        app,
+        addr,
+        get,
+        &format!("/users/@me/activity/data?paginate=true&limit=2&cursor={cursor}"),
+        user.auth_token
+    );
+    assert!(resp.status().is_success(), "Getting second page failed");
+
+    let page: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(page["data"].as_array().unwrap().len(), 1);
+    assert_eq!(page["data"][0]["project_name"], "project 2");
+    assert!(
+        page["next_cursor"].is_null(),
+        "Last page has no next cursor"
+    );
+
+    let resp = request_auth!(
+        app,
+        addr,
+        get,
+        "/users/@me/activity/data?paginate=true&limit=1&order=desc",
+        user.auth_token
+    );
+    let page: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(page["data"][0]["project_name"], "project 2");
+
+    let resp = request_auth!(
+        app,
+        addr,
+        get,
+        "/users/@me/activity/data?limit=2",
+        user.auth_token
+    );
+    let data: Vec<serde_json::Value> = test::read_body_json(resp).await;
+    assert_eq!(
+        data.len(),
+        2,
+        "Limit without pagination should limit the array of activities"
+    );
+
+    let resp = request_auth!(
+        app,
+        addr,
+        get,
+        "/users/@me/activity/data?limit=1&cursor=invalid",
+        user.auth_token
+    );
+    assert!(
+        resp.status().is_client_error(),
+        "Invalid cursor should be rejected"
+    );
+
+    let resp = request!(app, addr, delete, "/users/@me/delete", body);
+    assert!(resp.status().is_success(), "Failed to delete user");
+}
+
+#[actix_web::test]
+async fn activity_aggregation_works() {
+    let app = test::init_service(App::new().configure(init_test_services)).await;
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 80u16);
+
+    let body = json!({"username": "aggregateuser", "password": "password"});
+    let resp = request!(app, addr, post, "/auth/register", body);
+    let user: NewUserIdentity = test::read_body_json(resp).await;
+
+    let start = chrono::Utc::now().timestamp() - 3600;
+    let heartbeat = |offset: i64, project: &str, language: &str| {
+        json!({
+            "timestamp": start + offset,
+            "project_name": project,
+            "language": language,
+            "editor_name": "nvim",
+            "hostname": "hostname",
+        })
+    };
+    let batch = json!([
+        heartbeat(0, "cool project", "rust"),
+        heartbeat(60, "cool project", "rust"),
+        heartbeat(1000, "another project", "python"),
+        heartbeat(1100, "another project", "python"),
+    ]);
+
+    let resp = request_auth!(app, addr, post, "/activity/batch", user.auth_token, batch);
+    assert!(resp.status().is_success(), "Sending a batch should succeed");
+
+    let resp = request_auth!(
+        app,
+        addr,
+        get,
+        "/users/@me/activity/aggregate?group_by=language,editor",
+        user.auth_token
+    );
+    assert!(resp.status().is_success(), "Aggregating activity failed");
+
+    let aggregate: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(
+        aggregate,
+        json!([
+            {"language": "python", "editor": "nvim", "total": 100},
+            {"language": "rust", "editor": "nvim", "total": 60},
+        ]),
+        "Activity should be summed by the grouped fields"
+    );
+
+    let resp = request_auth!(
+        app,
+        addr,
+        get,
+        "/users/@me/activity/aggregate?group_by=project&bucket=hour",
+        user.auth_token
+    );
+    let aggregate: serde_json::Value = test::read_body_json(resp).await;
+    let buckets = aggregate.as_array().unwrap();
+    assert!(
+        buckets.iter().all(|b| b["bucket"].is_string()),
+        "Bucketed aggregates should have a bucket"
+    );
+    assert_eq!(
+        buckets
+            .iter()
+            .map(|b| b["total"].as_i64().unwrap())
+            .sum::<i64>(),
+        160
+    );
+
+    let resp = request_auth!(
+        app,
+        addr,
+        get,
+        &format!("/users/@me/activity/aggregate?from={}", start + 500),
+        user.auth_token
+    );
+    let aggregate: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(
+        aggregate,
+        json!([{"total": 100}]),
+        "Activity before from should not be counted"
+    );
+
+    let resp = request_auth!(
+        app,
+        addr,
+        get,
+        "/users/@me/activity/aggregate?group_by=password",
+        user.auth_token
+    );
+    assert!(
+        resp.status().is_client_error(),
+        "Unknown fields should be rejected"
+    );
+
+    let resp = request!(app, addr, get, "/users/aggregateuser/activity/aggregate");
+    assert_eq!(
+        resp.status(),
+        404,
+        "Private users should not be visible without authentication"
+    );
+
+    let resp = request_auth!(
+        app,
+        addr,
+        get,
+        "/users/@me/activity/summary",
+        user.auth_token
+    );
+    let summary: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(summary["last_week"]["languages"]["rust"], 60);
+    assert_eq!(summary["all_time"]["total"], 160);
+
+    let resp = request!(app, addr, delete, "/users/@me/delete", body);
+    assert!(resp.status().is_success(), "Failed to delete user");
+}
+
+#[actix_web::test]
+async fn goals_and_streaks_work() {
+    let app = test::init_service(App::new().configure(init_test_services)).await;
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 80u16);
+
+    let body = json!({"username": "streakuser", "password": "password"});
+    let resp = request!(app, addr, post, "/auth/register", body);
+    let user: NewUserIdentity = test::read_body_json(resp).await;
+
+    let today = chrono::Utc::now().date_naive();
+    let mut batch = Vec::new();
+    // Three days in a row, a day with too little coding and then two days until yesterday
+    for (days_ago, minutes) in [(7, 20), (6, 20), (5, 20), (3, 5), (2, 20), (1, 20)] {
+        let start = (today - chrono::Duration::days(days_ago))
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+            .and_utc()
+            .timestamp();
+        for minute in (0..=minutes).step_by(5) {
+            batch.push(json!({"timestamp": start + minute * 60, "project_name": "streak"}));
+        }
+    }
+
+    let resp = request_auth!(app, addr, post, "/activity/batch", user.auth_token, batch);
+    assert!(resp.status().is_success(), "Sending a batch should succeed");
+
+    let resp = request_auth!(app, addr, get, "/users/@me/streak", user.auth_token);
+    assert!(resp.status().is_success(), "Getting streak failed");
+    let streak: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(
+        streak["current"], 2,
+        "Current streak should include yesterday"
+    );
+    assert_eq!(streak["longest"], 3);
+
+    let resp = request_auth!(
+        app,
+        addr,
+        get,
+        "/users/@me/streak?min_minutes=1",
+        user.auth_token
+    );
+    let streak: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(
+        streak["current"], 3,
+        "Shorter days should count with a smaller minimum"
+    );
+
+    let goals = json!({"daily_goal": 3600, "weekly_goal": 7200});
+    let resp = request_auth!(app, addr, post, "/users/@me/goals", user.auth_token, goals);
+    assert!(
+        resp.status().is_client_error(),
+        "Setting goals should require secured access"
+    );
+
+    let resp = request!(app, addr, post, "/auth/securedaccess", body);
+    let sat: SecuredAccessTokenResponse = test::read_body_json(resp).await;
+
+    let too_long = json!({"daily_goal": 60 * 60 * 25});
+    let resp = request_auth!(app, addr, post, "/users/@me/goals", sat.token, too_long);
+    assert_eq!(
+        resp.status(),
+        400,
+        "Daily goals over a day should be rejected"
+    );
+
+    let resp = request_auth!(app, addr, post, "/users/@me/goals", sat.token, goals);
+    assert!(resp.status().is_success(), "Setting goals failed");
+
+    let resp = request_auth!(app, addr, get, "/users/@me/goals", user.auth_token);
+    let goals: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(goals["daily_goal"], 3600);
+    assert_eq!(goals["weekly_goal"], 7200);
+    assert_eq!(goals["today"], 0);
+    assert_eq!(goals["daily_goal_reached"], false);
+
+    let resp = request!(app, addr, get, "/users/streakuser/goals");
+    assert_eq!(
+        resp.status(),
+        404,
+        "Goals of private users should not be visible"
+    );
+
+    let resp = request!(app, addr, delete, "/users/@me/delete", body);
+    assert!(resp.status().is_success(), "Failed to delete user");
+}
+
 // TODO: write tests for /activity/delete and /activity/rename
+
+#[actix_web::test]
+async fn project_aliases_work() {
+    let app = test::init_service(App::new().configure(init_test_services)).await;
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 80u16);
+
+    let body = json!({"username": "aliasuser", "password": "password"});
+    let resp = request!(app, addr, post, "/auth/register", body);
+    let user: NewUserIdentity = test::read_body_json(resp).await;
+
+    let start = chrono::Utc::now().timestamp() - 3600;
+    let batch = json!([
+        {
+            "timestamp": start,
+            "project_name": "old name",
+            "language": "rust",
+        },
+        {
+            "timestamp": start + 60,
+            "project_name": "old name",
+            "language": "rust",
+        },
+    ]);
+    let resp = request_auth!(app, addr, post, "/activity/batch", user.auth_token, batch);
+    assert!(resp.status().is_success(), "Sending a batch should succeed");
+
+    let alias = json!({"pattern": "client-*", "match_kind": "glob", "target": "client"});
+    let resp = request_auth!(app, addr, post, "/activity/aliases", user.auth_token, alias);
+    assert!(resp.status().is_success(), "Adding an alias should work");
+    let glob: serde_json::Value = test::read_body_json(resp).await;
+
+    let resp = request_auth!(app, addr, post, "/activity/aliases", user.auth_token, alias);
+    assert_eq!(resp.status(), 409, "Duplicate patterns should be rejected");
+
+    let invalid = json!({"pattern": "(", "match_kind": "regex", "target": "client"});
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/activity/aliases",
+        user.auth_token,
+        invalid
+    );
+    assert_eq!(resp.status(), 400, "Invalid regexes should be rejected");
+
+    let exact = json!({"pattern": "old name", "target": "new name", "backfill": true});
+    let resp = request_auth!(app, addr, post, "/activity/aliases", user.auth_token, exact);
+    assert!(resp.status().is_success(), "Adding an alias should work");
+    let result: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(
+        result["affected_activities"], 1,
+        "Existing activities should be renamed"
+    );
+
+    let resp = request_auth!(app, addr, get, "/activity/aliases", user.auth_token);
+    let aliases: Vec<serde_json::Value> = test::read_body_json(resp).await;
+    assert_eq!(aliases.len(), 2);
+    assert_eq!(aliases[0]["match_kind"], "glob");
+
+    let heartbeat = json!({"project_name": "client-web", "language": "rust"});
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/activity/update",
+        user.auth_token,
+        heartbeat
+    );
+    assert!(resp.status().is_success(), "Sending heartbeat should work");
+
+    let resp = request_auth!(
+        app,
+        addr,
+        get,
+        "/users/@me/activity/current",
+        user.auth_token
+    );
+    let current: CurrentActivity = test::read_body_json(resp).await;
+    assert_eq!(
+        current.heartbeat.project_name.as_deref(),
+        Some("client"),
+        "Aliases should be applied to heartbeats"
+    );
+
+    let resp = request_auth!(app, addr, get, "/users/@me/activity/data", user.auth_token);
+    let data: Vec<serde_json::Value> = test::read_body_json(resp).await;
+    assert!(data.iter().all(|a| a["project_name"] == "new name"));
+
+    let uri = format!("/activity/aliases/{}", glob["alias"]["id"]);
+    let resp = request_auth!(app, addr, delete, &uri, user.auth_token);
+    assert!(resp.status().is_success(), "Removing an alias should work");
+
+    let resp = request_auth!(app, addr, delete, &uri, user.auth_token);
+    assert_eq!(resp.status(), 400, "Removed aliases should not be found");
+
+    let resp = request!(app, addr, delete, "/users/@me/delete", body);
+    assert!(resp.status().is_success(), "Failed to delete user");
+}
+
+#[actix_web::test]
+async fn bulk_editing_activities_works() {
+    let app = test::init_service(App::new().configure(init_test_services)).await;
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 80u16);
+
+    let body = json!({"username": "bulkuser", "password": "password"});
+    let resp = request!(app, addr, post, "/auth/register", body);
+    let user: NewUserIdentity = test::read_body_json(resp).await;
+
+    let start = chrono::Utc::now().timestamp() - 3600;
+    let batch = json!([
+        {"timestamp": start, "project_name": "a", "hostname": "wrong host"},
+        {"timestamp": start + 30, "project_name": "a", "hostname": "wrong host"},
+        {"timestamp": start + 60, "project_name": "b", "hostname": "wrong host"},
+        {"timestamp": start + 90, "project_name": "b", "hostname": "wrong host"},
+        {"timestamp": start + 120, "project_name": "c", "hostname": "right host"},
+        {"timestamp": start + 150, "project_name": "c", "hostname": "right host"},
+    ]);
+    let resp = request_auth!(app, addr, post, "/activity/batch", user.auth_token, batch);
+    let result: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(result["sessions"], 3);
+
+    let update = json!({
+        "filter": {"hostname": "wrong host"},
+        "set": {"hostname": "right host"},
+        "dry_run": true,
+    });
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/activity/bulk/update",
+        user.auth_token,
+        update
+    );
+    assert!(
+        resp.status().is_client_error(),
+        "Bulk editing should require secured access"
+    );
+
+    let resp = request!(app, addr, post, "/auth/securedaccess", body);
+    let sat: SecuredAccessTokenResponse = test::read_body_json(resp).await;
+
+    let resp = request_auth!(app, addr, post, "/activity/bulk/update", sat.token, update);
+    let result: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(
+        result["affected_activities"], 2,
+        "Preview should count matches"
+    );
+
+    let resp = request_auth!(app, addr, get, "/users/@me/activity/data", user.auth_token);
+    let data: Vec<serde_json::Value> = test::read_body_json(resp).await;
+    assert_eq!(
+        data.iter()
+            .filter(|a| a["hostname"] == "wrong host")
+            .count(),
+        2,
+        "Dry runs should not change activities"
+    );
+
+    let heartbeat = json!({"project_name": "d", "hostname": "wrong host"});
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/activity/update",
+        user.auth_token,
+        heartbeat
+    );
+    assert!(resp.status().is_success(), "Sending heartbeat failed");
+
+    let update = json!({
+        "filter": {"hostname": "wrong host"},
+        "set": {"hostname": "right host"},
+    });
+    let resp = request_auth!(app, addr, post, "/activity/bulk/update", sat.token, update);
+    let result: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(result["affected_activities"], 2);
+
+    let resp = request_auth!(app, addr, post, "/activity/flush", user.auth_token);
+    assert!(resp.status().is_success(), "Flushing failed");
+
+    let empty = json!({"filter": {"hostname": "right host"}, "set": {}});
+    let resp = request_auth!(app, addr, post, "/activity/bulk/update", sat.token, empty);
+    assert_eq!(resp.status(), 400, "Updates without changes should fail");
+
+    let unfiltered = json!({"filter": {}});
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/activity/bulk/delete",
+        sat.token,
+        unfiltered
+    );
+    assert_eq!(resp.status(), 400, "Deleting without a filter should fail");
+
+    let paginated = json!({"filter": {"limit": 1}});
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/activity/bulk/delete",
+        sat.token,
+        paginated
+    );
+    assert_eq!(
+        resp.status(),
+        400,
+        "Pagination should not count as a filter"
+    );
+
+    let delete = json!({"filter": {"project_name": "a"}});
+    let resp = request_auth!(app, addr, post, "/activity/bulk/delete", sat.token, delete);
+    let result: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(result["affected_activities"], 1);
+
+    let resp = request_auth!(app, addr, get, "/users/@me/activity/data", user.auth_token);
+    let data: Vec<serde_json::Value> = test::read_body_json(resp).await;
+    assert_eq!(data.len(), 3, "Matching activities should be deleted");
+    assert!(
+        data.iter().all(|a| a["hostname"] == "right host"),
+        "The current session should be changed too"
+    );
+
+    let resp = request!(app, addr, delete, "/users/@me/delete", body);
+    assert!(resp.status().is_success(), "Failed to delete user");
+}
+
+#[actix_web::test]
+async fn manual_activities_work() {
+    let app = test::init_service(App::new().configure(init_test_services)).await;
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 80u16);
+
+    let body = json!({"username": "manualuser", "password": "password"});
+    let resp = request!(app, addr, post, "/auth/register", body);
+    let user: NewUserIdentity = test::read_body_json(resp).await;
+
+    let start = chrono::Utc::now().timestamp() - 7200;
+    let entry = json!({"start_time": start, "duration": 1800, "project_name": "whiteboard"});
+    let resp = request_auth!(app, addr, post, "/activity/manual", user.auth_token, entry);
+    assert!(
+        resp.status().is_success(),
+        "Adding a manual activity failed"
+    );
+    let activity: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(activity["source"], "manual");
+
+    let future = json!({"start_time": start, "duration": 7300});
+    let resp = request_auth!(app, addr, post, "/activity/manual", user.auth_token, future);
+    assert_eq!(resp.status(), 400, "Activities in the future should fail");
+
+    let empty = json!({"start_time": start, "duration": 0});
+    let resp = request_auth!(app, addr, post, "/activity/manual", user.auth_token, empty);
+    assert_eq!(resp.status(), 400, "Empty activities should fail");
+
+    let overlapping = json!({"start_time": start + 900, "duration": 1800});
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/activity/manual",
+        user.auth_token,
+        overlapping
+    );
+    assert_eq!(
+        resp.status(),
+        409,
+        "Overlapping activities should not be added"
+    );
+
+    let tmp = json!({"start_time": start - 3600, "duration": 60, "project_name": "tmp.abc"});
+    let resp = request_auth!(app, addr, post, "/activity/manual", user.auth_token, tmp);
+    let tmp: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(
+        tmp["project_name"], "tmp",
+        "Project names should be normalized"
+    );
+
+    let tmp_uri = format!("/activity/manual/{}", tmp["id"]);
+    let resp = request_auth!(app, addr, delete, &tmp_uri, user.auth_token);
+    assert!(resp.status().is_success());
+
+    let uri = format!("/activity/manual/{}", activity["id"]);
+    let entry = json!({"start_time": start, "duration": 3600, "project_name": "pairing"});
+    let resp = request_auth!(app, addr, post, &uri, user.auth_token, entry);
+    assert!(
+        resp.status().is_success(),
+        "Editing a manual activity failed"
+    );
+    let activity: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(activity["duration"], 3600);
+    assert_eq!(activity["project_name"], "pairing");
+
+    let create = json!({"name": "manualboard", "time_window": "all_time"});
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/create",
+        user.auth_token,
+        create
+    );
+    assert!(resp.status().is_success(), "Leaderboard creation failed");
+
+    let resp = request_auth!(app, addr, get, "/users/@me/leaderboards", user.auth_token);
+    let boards: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(
+        boards[0]["me"]["time_coded"], 3600,
+        "Manual activities should be counted by default"
+    );
+
+    let resp = request!(app, addr, post, "/auth/securedaccess", body);
+    let sat: SecuredAccessTokenResponse = test::read_body_json(resp).await;
+
+    let settings = json!({"exclude_manual": true});
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/manualboard/settings",
+        sat.token,
+        settings
+    );
+    assert!(resp.status().is_success(), "Changing settings failed");
+
+    let resp = request_auth!(app, addr, get, "/users/@me/leaderboards", user.auth_token);
+    let boards: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(
+        boards[0]["me"]["time_coded"], 0,
+        "Manual activities should be excluded"
+    );
+
+    let resp = request_auth!(app, addr, delete, "/leaderboards/manualboard", sat.token);
+    assert!(resp.status().is_success(), "Failed to delete leaderboard");
+
+    let resp = request_auth!(app, addr, delete, &uri, user.auth_token);
+    assert!(
+        resp.status().is_success(),
+        "Deleting a manual activity failed"
+    );
+
+    let resp = request_auth!(app, addr, delete, &uri, user.auth_token);
+    assert_eq!(resp.status(), 400, "Deleted activities should not be found");
+
+    let resp = request!(app, addr, delete, "/users/@me/delete", body);
+    assert!(resp.status().is_success(), "Failed to delete user");
+}
@@ -4,7 +4,7 @@ use actix_web::test::{self, TestRequest};
 use serde_json::json;
 
 use super::{macros::*, *};
-use crate::models::NewUserIdentity;
+use crate::models::{BlockedUser, FriendRequests, NewUserIdentity, SecuredAccessTokenResponse};
 
 #[actix_web::test]
 async fn adding_friends_works() {
@@ -31,7 +31,11 @@ async fn adding_friends_works() {
         .send_request(&app)
         .await;
 
-    assert!(resp.status().is_success(), "Adding friend works");
+    assert_eq!(
+        resp.status(),
+        StatusCode::ACCEPTED,
+        "Adding friend sends a request"
+    );
 
     let resp = TestRequest::post()
         .peer_addr(addr)
@@ -41,7 +45,7 @@ async fn adding_friends_works() {
         .send_request(&app)
         .await;
 
-    assert!(resp.status().is_client_error(), "Re-adding friend fails");
+    assert!(resp.status().is_client_error(), "Re-sending request fails");
 
     let resp = TestRequest::post()
         .peer_addr(addr)
@@ -57,7 +61,35 @@ async fn adding_friends_works() {
         app,
         addr,
         get,
-        &format!("/users/{}/activity/data", &f1.username),
+        &format!("/users/{}/activity/data", f1.username),
+        f2.auth_token
+    );
+    assert!(
+        resp.status().is_client_error(),
+        "Pending friends can't see eachothers data"
+    );
+
+    let resp = request_auth!(app, other_addr, get, "/friends/requests", f2.auth_token);
+    let requests: FriendRequests = test::read_body_json(resp).await;
+    assert_eq!(requests.incoming.len(), 1, "Request appears as incoming");
+    assert_eq!(requests.incoming[0].username, "friend1");
+    assert!(requests.outgoing.is_empty());
+
+    let resp = TestRequest::post()
+        .peer_addr(other_addr)
+        .uri("/friends/requests/accept")
+        .insert_header(("authorization", "Bearer ".to_owned() + &f2.auth_token))
+        .set_payload("friend1")
+        .send_request(&app)
+        .await;
+
+    assert!(resp.status().is_success(), "Accepting request works");
+
+    let resp = request_auth!(
+        app,
+        addr,
+        get,
+        &format!("/users/{}/activity/data", f1.username),
         f2.auth_token
     );
     assert!(
@@ -78,4 +110,348 @@ async fn adding_friends_works() {
     assert!(resp.status().is_success(), "Failed to delete user");
 }
 
+#[actix_web::test]
+async fn declining_and_blocking_friend_requests_works() {
+    let app = test::init_service(App::new().configure(init_test_services)).await;
+
+    let f1_body = json!({"username": "requester", "password": "password"});
+    let f2_body = json!({"username": "requested", "password": "password"});
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 80u16);
+    let other_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)), 80u16);
+
+    let resp = request!(app, addr, post, "/auth/register", f1_body);
+    let f1: NewUserIdentity = test::read_body_json(resp).await;
+
+    let resp = request!(app, other_addr, post, "/auth/register", f2_body);
+    let f2: NewUserIdentity = test::read_body_json(resp).await;
+
+    let resp = TestRequest::post()
+        .peer_addr(addr)
+        .uri("/friends/add")
+        .insert_header(("authorization", "Bearer ".to_owned() + &f1.auth_token))
+        .set_payload("requested")
+        .send_request(&app)
+        .await;
+    assert_eq!(
+        resp.status(),
+        StatusCode::ACCEPTED,
+        "Requests can be sent by username"
+    );
+
+    let resp = request_auth!(app, addr, get, "/friends/requests", f1.auth_token);
+    let requests: FriendRequests = test::read_body_json(resp).await;
+    assert_eq!(requests.outgoing.len(), 1, "Request appears as outgoing");
+
+    let resp = TestRequest::post()
+        .peer_addr(other_addr)
+        .uri("/friends/requests/decline")
+        .insert_header(("authorization", "Bearer ".to_owned() + &f2.auth_token))
+        .set_payload("requester")
+        .send_request(&app)
+        .await;
+    assert!(resp.status().is_success(), "Declining request works");
+
+    let resp = request_auth!(app, addr, get, "/friends/requests", f1.auth_token);
+    let requests: FriendRequests = test::read_body_json(resp).await;
+    assert!(
+        requests.outgoing.is_empty(),
+        "Declined requests are removed"
+    );
+
+    let resp = TestRequest::post()
+        .peer_addr(addr)
+        .uri("/friends/add")
+        .insert_header(("authorization", "Bearer ".to_owned() + &f1.auth_token))
+        .set_payload(f2.friend_code.clone())
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::ACCEPTED);
+
+    let resp = TestRequest::post()
+        .peer_addr(other_addr)
+        .uri("/friends/requests/block")
+        .insert_header(("authorization", "Bearer ".to_owned() + &f2.auth_token))
+        .set_payload("requester")
+        .send_request(&app)
+        .await;
+    assert!(resp.status().is_success(), "Blocking requester works");
+
+    let resp = TestRequest::post()
+        .peer_addr(addr)
+        .uri("/friends/add")
+        .insert_header(("authorization", "Bearer ".to_owned() + &f1.auth_token))
+        .set_payload(f2.friend_code.clone())
+        .send_request(&app)
+        .await;
+    assert_eq!(
+        resp.status(),
+        StatusCode::NOT_FOUND,
+        "Blocked users can't send requests"
+    );
+
+    let resp = TestRequest::post()
+        .peer_addr(other_addr)
+        .uri("/friends/requests/accept")
+        .insert_header(("authorization", "Bearer ".to_owned() + &f2.auth_token))
+        .set_payload("requester")
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    let resp = request!(app, addr, delete, "/users/@me/delete", f1_body);
+    assert!(resp.status().is_success(), "Failed to delete user");
+
+    let resp = request!(app, addr, delete, "/users/@me/delete", f2_body);
+    assert!(resp.status().is_success(), "Failed to delete user");
+}
+
+#[actix_web::test]
+async fn friend_visibility_and_blocking_works() {
+    let app = test::init_service(App::new().configure(init_test_services)).await;
+
+    let f1_body = json!({"username": "secretive", "password": "password"});
+    let f2_body = json!({"username": "curious", "password": "password"});
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 80u16);
+    let other_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)), 80u16);
+
+    let resp = request!(app, addr, post, "/auth/register", f1_body);
+    let f1: NewUserIdentity = test::read_body_json(resp).await;
+
+    let resp = request!(app, other_addr, post, "/auth/register", f2_body);
+    let f2: NewUserIdentity = test::read_body_json(resp).await;
+
+    let resp = TestRequest::post()
+        .peer_addr(addr)
+        .uri("/friends/add")
+        .insert_header(("authorization", "Bearer ".to_owned() + &f1.auth_token))
+        .set_payload("curious")
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::ACCEPTED);
+
+    let resp = TestRequest::post()
+        .peer_addr(other_addr)
+        .uri("/friends/add")
+        .insert_header(("authorization", "Bearer ".to_owned() + &f2.auth_token))
+        .set_payload("secretive")
+        .send_request(&app)
+        .await;
+    assert_eq!(
+        resp.status(),
+        StatusCode::OK,
+        "Requesting back accepts the request"
+    );
+
+    let heartbeat = json!({
+        "project_name": "client project",
+        "language": "rust",
+        "editor_name": "nvim",
+        "hostname": "work laptop",
+    });
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/activity/update",
+        f1.auth_token,
+        heartbeat
+    );
+    assert!(resp.status().is_success(), "Sending heartbeat failed");
+    let resp = request_auth!(app, addr, post, "/activity/flush", f1.auth_token);
+    assert!(resp.status().is_success(), "Flushing failed");
+
+    let visibility = json!({"hide_project_names": true, "hide_hostnames": true});
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/friends/curious/visibility",
+        f1.auth_token,
+        visibility
+    );
+    assert!(resp.status().is_success(), "Changing visibility failed");
+
+    let resp = request_auth!(
+        app,
+        other_addr,
+        get,
+        "/users/secretive/activity/data",
+        f2.auth_token
+    );
+    let activities: Vec<serde_json::Value> = test::read_body_json(resp).await;
+    assert_eq!(activities.len(), 1);
+    assert_eq!(
+        activities[0]["project_name"], "private project",
+        "Project is hidden"
+    );
+    assert_eq!(
+        activities[0]["hostname"], "private host",
+        "Hostname is hidden"
+    );
+    assert_eq!(activities[0]["editor_name"], "nvim");
+
+    let resp = request_auth!(
+        app,
+        other_addr,
+        get,
+        "/users/secretive/activity/data?project_name=client%20project",
+        f2.auth_token
+    );
+    assert!(
+        resp.status().is_client_error(),
+        "Hidden fields can't be filtered by"
+    );
+
+    let resp = request_auth!(
+        app,
+        other_addr,
+        get,
+        "/users/secretive/activity/aggregate?group_by=project,language",
+        f2.auth_token
+    );
+    let aggregate: Vec<serde_json::Value> = test::read_body_json(resp).await;
+    assert_eq!(aggregate.len(), 1);
+    assert_eq!(
+        aggregate[0]["project"], "private project",
+        "Project is hidden"
+    );
+    assert_eq!(aggregate[0]["language"], "rust");
+
+    let resp = request_auth!(app, addr, get, "/users/@me/activity/data", f1.auth_token);
+    let activities: Vec<serde_json::Value> = test::read_body_json(resp).await;
+    assert_eq!(
+        activities[0]["project_name"], "client project",
+        "The owner sees everything"
+    );
+
+    let resp = request!(app, addr, post, "/auth/securedaccess", f1_body);
+    let sat: SecuredAccessTokenResponse = test::read_body_json(resp).await;
+    let change = json!({"public_profile": true});
+    let resp = request_auth!(app, addr, post, "/account/settings", sat.token, change);
+    assert!(resp.status().is_success(), "Changing settings failed");
+
+    let resp = request_auth!(
+        app,
+        other_addr,
+        post,
+        "/users/@me/blocks",
+        f2.auth_token,
+        json!({"username": "secretive"})
+    );
+    assert!(resp.status().is_success(), "Blocking failed");
+
+    let resp = request_auth!(app, addr, get, "/friends/list", f1.auth_token);
+    let friends: Vec<serde_json::Value> = test::read_body_json(resp).await;
+    assert!(friends.is_empty(), "Blocking removes the friendship");
+
+    let resp = request_auth!(
+        app,
+        addr,
+        get,
+        "/users/curious/activity/data",
+        f1.auth_token
+    );
+    assert!(resp.status().is_client_error());
+
+    let resp = request_auth!(
+        app,
+        other_addr,
+        get,
+        "/users/secretive/activity/data",
+        f2.auth_token
+    );
+    assert_eq!(
+        resp.status(),
+        StatusCode::NOT_FOUND,
+        "Blocked users are hidden even if public"
+    );
+
+    let resp = request_auth!(
+        app,
+        other_addr,
+        get,
+        "/search/users?keyword=secretive",
+        f2.auth_token
+    );
+    let users: Vec<serde_json::Value> = test::read_body_json(resp).await;
+    assert!(users.is_empty(), "Blocked users are hidden from search");
+
+    let resp = request_auth!(app, other_addr, get, "/users/@me/blocks", f2.auth_token);
+    let blocks: Vec<BlockedUser> = test::read_body_json(resp).await;
+    assert_eq!(blocks.len(), 1);
+    assert_eq!(blocks[0].username, "secretive");
+
+    let resp = request_auth!(
+        app,
+        other_addr,
+        delete,
+        "/users/@me/blocks/secretive",
+        f2.auth_token
+    );
+    assert!(resp.status().is_success(), "Unblocking failed");
+
+    let resp = request_auth!(
+        app,
+        other_addr,
+        get,
+        "/search/users?keyword=secretive",
+        f2.auth_token
+    );
+    let users: Vec<serde_json::Value> = test::read_body_json(resp).await;
+    assert_eq!(users.len(), 1);
+
+    let resp = request!(app, addr, delete, "/users/@me/delete", f1_body);
+    assert!(resp.status().is_success(), "Failed to delete user");
+
+    let resp = request!(app, addr, delete, "/users/@me/delete", f2_body);
+    assert!(resp.status().is_success(), "Failed to delete user");
+}
+
 // TODO: write tests for /friends/regenerate and /friends/remove
+
+#[actix_web::test]
+async fn simultaneous_friend_requests_are_accepted() {
+    let app = test::init_service(App::new().configure(init_test_services)).await;
+
+    let f1_body = json!({"username": "racefriend1", "password": "password"});
+    let f2_body = json!({"username": "racefriend2", "password": "password"});
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 80u16);
+    let other_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)), 80u16);
+
+    let resp = request!(app, addr, post, "/auth/register", f1_body);
+    let f1: NewUserIdentity = test::read_body_json(resp).await;
+
+    let resp = request!(app, other_addr, post, "/auth/register", f2_body);
+    let f2: NewUserIdentity = test::read_body_json(resp).await;
+
+    let (resp1, resp2) = futures::join!(
+        TestRequest::post()
+            .peer_addr(addr)
+            .uri("/friends/add")
+            .insert_header(("authorization", "Bearer ".to_owned() + &f1.auth_token))
+            .set_payload(f2.friend_code.clone())
+            .send_request(&app),
+        TestRequest::post()
+            .peer_addr(other_addr)
+            .uri("/friends/add")
+            .insert_header(("authorization", "Bearer ".to_owned() + &f2.auth_token))
+            .set_payload(f1.friend_code.clone())
+            .send_request(&app)
+    );
+    assert!(resp1.status().is_success(), "Sending a request failed");
+    assert!(resp2.status().is_success(), "Sending a request failed");
+
+    let resp = request_auth!(app, addr, get, "/friends/list", f1.auth_token);
+    let friends: Vec<serde_json::Value> = test::read_body_json(resp).await;
+    assert_eq!(
+        friends.len(),
+        1,
+        "Simultaneous requests should make the users friends"
+    );
+
+    let resp = request!(app, addr, delete, "/users/@me/delete", f1_body);
+    assert!(resp.status().is_success(), "Failed to delete user");
+
+    let resp = request!(app, other_addr, delete, "/users/@me/delete", f2_body);
+    assert!(resp.status().is_success(), "Failed to delete user");
+}
@@ -6,7 +6,10 @@ use serde_json::json;
 use super::{macros::*, *};
 use crate::{
     api::leaderboards::{LeaderboardInvite, LeaderboardName},
-    models::{NewUserIdentity, PrivateLeaderboard, SecuredAccessTokenResponse},
+    models::{
+        InviteLink, JoinRequest, LeaderboardBan, NewUserIdentity, PrivateLeaderboard,
+        SeasonSnapshot, SeasonSummary, SecuredAccessTokenResponse,
+    },
 };
 
 #[actix_web::test]
@@ -124,3 +127,1281 @@ async fn creation_joining_and_deletion() {
 }
 
 // TODO: add tests for all the leaderboards endpoints
+
+#[actix_web::test]
+async fn leaderboard_windows_and_metrics_work() {
+    let app = test::init_service(App::new().configure(init_test_services)).await;
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 80u16);
+
+    let body = json!({"username": "metricowner", "password": "password"});
+    let resp = request!(app, addr, post, "/auth/register", body);
+    let user: NewUserIdentity = test::read_body_json(resp).await;
+
+    let today = chrono::Utc::now().date_naive();
+    let mut batch = Vec::new();
+    for (days_ago, project) in [(3, "first"), (2, "second")] {
+        let start = (today - chrono::Duration::days(days_ago))
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+            .and_utc()
+            .timestamp();
+        for minute in [0, 5, 10] {
+            batch.push(json!({"timestamp": start + minute * 60, "project_name": project}));
+        }
+    }
+    let resp = request_auth!(app, addr, post, "/activity/batch", user.auth_token, batch);
+    assert!(resp.status().is_success(), "Sending a batch should succeed");
+
+    let create = json!({"name": "metricboard", "time_window": "all_time", "metric": "projects"});
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/create",
+        user.auth_token,
+        create
+    );
+    assert!(resp.status().is_success(), "Leaderboard creation failed");
+
+    let resp = request_auth!(app, addr, get, "/leaderboards/metricboard", user.auth_token);
+    let board: PrivateLeaderboard = test::read_body_json(resp).await;
+    assert_eq!(board.members[0].score, 2, "Projects should be counted");
+    assert_eq!(board.members[0].time_coded, 1200);
+
+    let resp = request!(app, addr, post, "/auth/securedaccess", body);
+    let sat: SecuredAccessTokenResponse = test::read_body_json(resp).await;
+
+    let settings = json!({"time_window": "rolling"});
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/metricboard/settings",
+        sat.token,
+        settings
+    );
+    assert!(
+        resp.status().is_client_error(),
+        "Rolling windows without days should be rejected"
+    );
+
+    let settings = json!({"metric": "active_days"});
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/metricboard/settings",
+        sat.token,
+        settings
+    );
+    assert!(resp.status().is_success(), "Changing settings failed");
+
+    let resp = request_auth!(app, addr, get, "/leaderboards/metricboard", user.auth_token);
+    let board: PrivateLeaderboard = test::read_body_json(resp).await;
+    assert_eq!(board.members[0].score, 2, "Active days should be counted");
+
+    let settings = json!({"time_window": "rolling", "window_days": 1});
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/metricboard/settings",
+        sat.token,
+        settings
+    );
+    assert!(resp.status().is_success(), "Changing settings failed");
+
+    let resp = request_auth!(app, addr, get, "/users/@me/leaderboards", user.auth_token);
+    let boards: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(
+        boards[0]["me"]["time_coded"], 0,
+        "Activity outside of the window should not be counted"
+    );
+
+    let resp = request_auth!(app, addr, delete, "/leaderboards/metricboard", sat.token);
+    assert!(resp.status().is_success(), "Failed to delete leaderboard");
+
+    let resp = request!(app, addr, delete, "/users/@me/delete", body);
+    assert!(resp.status().is_success(), "Failed to delete user");
+}
+
+#[actix_web::test]
+async fn leaderboard_filters_work() {
+    let app = test::init_service(App::new().configure(init_test_services)).await;
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 80u16);
+
+    let body = json!({"username": "filterowner", "password": "password"});
+    let resp = request!(app, addr, post, "/auth/register", body);
+    let user: NewUserIdentity = test::read_body_json(resp).await;
+
+    let start = (chrono::Utc::now() - chrono::Duration::hours(2)).timestamp();
+    let mut batch = Vec::new();
+    for (offset, project, language) in [(0, "testaustime", "Rust"), (3600, "website", "typescript")]
+    {
+        for minute in [0, 5, 10] {
+            batch.push(json!({
+                "timestamp": start + offset + minute * 60,
+                "project_name": project,
+                "language": language,
+                "editor_name": "vscode",
+            }));
+        }
+    }
+    let resp = request_auth!(app, addr, post, "/activity/batch", user.auth_token, batch);
+    assert!(resp.status().is_success(), "Sending a batch should succeed");
+
+    let create = json!({"name": "filterboard", "time_window": "all_time", "languages": ["rust"]});
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/create",
+        user.auth_token,
+        create
+    );
+    assert!(resp.status().is_success(), "Leaderboard creation failed");
+
+    let resp = request_auth!(app, addr, get, "/leaderboards/filterboard", user.auth_token);
+    let board: PrivateLeaderboard = test::read_body_json(resp).await;
+    assert_eq!(
+        board.members[0].time_coded, 600,
+        "Only Rust time should be counted"
+    );
+
+    let resp = request!(app, addr, post, "/auth/securedaccess", body);
+    let sat: SecuredAccessTokenResponse = test::read_body_json(resp).await;
+
+    let settings = json!({"languages": [], "project_pattern": "web%", "editor": "vscode"});
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/filterboard/settings",
+        sat.token,
+        settings
+    );
+    assert!(resp.status().is_success(), "Changing filters failed");
+
+    let resp = request_auth!(app, addr, get, "/leaderboards/filterboard", user.auth_token);
+    let board: PrivateLeaderboard = test::read_body_json(resp).await;
+    assert_eq!(
+        board.config.languages, None,
+        "Empty filters should be removed"
+    );
+    assert_eq!(
+        board.members[0].time_coded, 600,
+        "Only matching projects should be counted"
+    );
+
+    let settings = json!({"project_pattern": "", "editor": "vim"});
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/filterboard/settings",
+        sat.token,
+        settings
+    );
+    assert!(resp.status().is_success(), "Changing filters failed");
+
+    let resp = request_auth!(app, addr, get, "/leaderboards/filterboard", user.auth_token);
+    let board: PrivateLeaderboard = test::read_body_json(resp).await;
+    assert_eq!(
+        board.members[0].time_coded, 0,
+        "Activity from other editors should not be counted"
+    );
+
+    let resp = request_auth!(app, addr, delete, "/leaderboards/filterboard", sat.token);
+    assert!(resp.status().is_success(), "Failed to delete leaderboard");
+
+    let resp = request!(app, addr, delete, "/users/@me/delete", body);
+    assert!(resp.status().is_success(), "Failed to delete user");
+}
+
+#[actix_web::test]
+async fn private_projects_are_not_ranked() {
+    let app = test::init_service(App::new().configure(init_test_services)).await;
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 80u16);
+
+    let body = json!({"username": "privateranked", "password": "password"});
+    let resp = request!(app, addr, post, "/auth/register", body);
+    let user: NewUserIdentity = test::read_body_json(resp).await;
+
+    let start = (chrono::Utc::now() - chrono::Duration::hours(2)).timestamp();
+    let batch = [0, 5, 10]
+        .map(|minute| json!({"timestamp": start + minute * 60, "project_name": "client-x"}));
+    let resp = request_auth!(app, addr, post, "/activity/batch", user.auth_token, batch);
+    assert!(resp.status().is_success(), "Sending a batch should succeed");
+
+    let resp = request!(app, addr, post, "/auth/securedaccess", body);
+    let sat: SecuredAccessTokenResponse = test::read_body_json(resp).await;
+
+    let privacy = json!({"private_projects": ["Client-X"]});
+    let resp = request_auth!(app, addr, post, "/account/privacy", sat.token, privacy);
+    assert!(
+        resp.status().is_success(),
+        "Changing privacy settings failed"
+    );
+
+    let create =
+        json!({"name": "probeboard", "time_window": "all_time", "project_pattern": "client%"});
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/create",
+        user.auth_token,
+        create
+    );
+    assert!(resp.status().is_success(), "Leaderboard creation failed");
+
+    let resp = request_auth!(app, addr, get, "/leaderboards/probeboard", user.auth_token);
+    let board: PrivateLeaderboard = test::read_body_json(resp).await;
+    assert_eq!(
+        board.members[0].score, 0,
+        "Private projects should not match the project filter"
+    );
+
+    let privacy = json!({"private_projects": [], "hide_project_names": true});
+    let resp = request_auth!(app, addr, post, "/account/privacy", sat.token, privacy);
+    assert!(
+        resp.status().is_success(),
+        "Changing privacy settings failed"
+    );
+
+    let settings = json!({"project_pattern": "other%"});
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/probeboard/settings",
+        sat.token,
+        settings
+    );
+    assert!(resp.status().is_success(), "Changing filters failed");
+
+    let resp = request_auth!(app, addr, get, "/leaderboards/probeboard", user.auth_token);
+    let board: PrivateLeaderboard = test::read_body_json(resp).await;
+    assert_eq!(
+        board.members[0].score, 0,
+        "The project filter should apply to hidden project names too"
+    );
+
+    let settings = json!({"project_pattern": ""});
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/probeboard/settings",
+        sat.token,
+        settings
+    );
+    assert!(resp.status().is_success(), "Changing filters failed");
+
+    let privacy = json!({"private_projects": ["Client-X"]});
+    let resp = request_auth!(app, addr, post, "/account/privacy", sat.token, privacy);
+    assert!(
+        resp.status().is_success(),
+        "Changing privacy settings failed"
+    );
+
+    let resp = request_auth!(app, addr, get, "/leaderboards/probeboard", user.auth_token);
+    let board: PrivateLeaderboard = test::read_body_json(resp).await;
+    assert_eq!(
+        board.members[0].score, 600,
+        "Private projects should be counted without a project filter"
+    );
+
+    let resp = request_auth!(app, addr, delete, "/leaderboards/probeboard", sat.token);
+    assert!(resp.status().is_success(), "Failed to delete leaderboard");
+
+    let resp = request!(app, addr, delete, "/users/@me/delete", body);
+    assert!(resp.status().is_success(), "Failed to delete user");
+}
+
+#[actix_web::test]
+async fn public_leaderboards_work() {
+    let app = test::init_service(App::new().configure(init_test_services)).await;
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 80u16);
+    let addr2 = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)), 80u16);
+
+    let owner_body = json!({"username": "publicowner", "password": "password"});
+    let resp = request!(app, addr, post, "/auth/register", owner_body);
+    let owner: NewUserIdentity = test::read_body_json(resp).await;
+
+    let member_body = json!({"username": "publicmember", "password": "password"});
+    let resp = request!(app, addr2, post, "/auth/register", member_body);
+    let member: NewUserIdentity = test::read_body_json(resp).await;
+
+    let create = json!({"name": "publicboard", "public": true});
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/create",
+        owner.auth_token,
+        create
+    );
+    assert!(resp.status().is_success(), "Leaderboard creation failed");
+
+    let create = json!({"name": "privateboard"});
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/create",
+        owner.auth_token,
+        create
+    );
+    assert!(resp.status().is_success(), "Leaderboard creation failed");
+
+    let resp = request!(app, addr2, get, "/leaderboards/search?keyword=board");
+    let boards: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(
+        boards,
+        json!([{"name": "publicboard", "member_count": 1}]),
+        "Only public leaderboards should be found"
+    );
+
+    for keyword in ["%25", "publ_cboard"] {
+        let resp = request!(
+            app,
+            addr2,
+            get,
+            &format!("/leaderboards/search?keyword={keyword}")
+        );
+        let boards: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(
+            boards,
+            json!([]),
+            "Wildcards in the search should be matched literally"
+        );
+    }
+
+    let resp = request!(app, addr2, get, "/leaderboards/publicboard");
+    assert!(
+        resp.status().is_success(),
+        "Public leaderboards should be visible without logging in"
+    );
+    let board: PrivateLeaderboard = test::read_body_json(resp).await;
+    assert_eq!(
+        board.invite, None,
+        "The invite should not be shown to outsiders"
+    );
+    assert_eq!(board.members.len(), 1);
+
+    let resp = request!(app, addr2, get, "/leaderboards/privateboard");
+    assert!(
+        resp.status().is_client_error(),
+        "Private leaderboards should not be visible without logging in"
+    );
+
+    let resp = request_auth!(
+        app,
+        addr2,
+        post,
+        "/leaderboards/privateboard/join",
+        member.auth_token
+    );
+    assert!(
+        resp.status().is_client_error(),
+        "Private leaderboards should not be joinable by name"
+    );
+
+    let resp = request_auth!(
+        app,
+        addr2,
+        post,
+        "/leaderboards/publicboard/join",
+        member.auth_token
+    );
+    assert!(resp.status().is_success(), "Joining by name failed");
+
+    let resp = request_auth!(
+        app,
+        addr2,
+        post,
+        "/leaderboards/publicboard/join",
+        member.auth_token
+    );
+    assert!(
+        resp.status().is_client_error(),
+        "Joining twice should not be possible"
+    );
+
+    let resp = request_auth!(
+        app,
+        addr2,
+        get,
+        "/leaderboards/publicboard",
+        member.auth_token
+    );
+    let board: PrivateLeaderboard = test::read_body_json(resp).await;
+    assert!(board.invite.is_some(), "Members should see the invite");
+    assert_eq!(board.members.len(), 2);
+
+    let resp = request!(app, addr2, get, "/leaderboards/publicboard");
+    let board: PrivateLeaderboard = test::read_body_json(resp).await;
+    assert_eq!(
+        board.members.len(),
+        1,
+        "Members with private accounts should be hidden from outsiders"
+    );
+
+    let resp = request!(app, addr2, post, "/auth/securedaccess", member_body);
+    let member_sat: SecuredAccessTokenResponse = test::read_body_json(resp).await;
+    let change = json!({"public_profile": true});
+    let resp = request_auth!(
+        app,
+        addr2,
+        post,
+        "/account/settings",
+        member_sat.token,
+        change
+    );
+    assert!(resp.status().is_success(), "Changing settings failed");
+
+    let resp = request!(app, addr2, get, "/leaderboards/publicboard");
+    let board: PrivateLeaderboard = test::read_body_json(resp).await;
+    assert_eq!(
+        board.members.len(),
+        2,
+        "Members with public accounts should be shown to outsiders"
+    );
+
+    let resp = request!(app, addr, post, "/auth/securedaccess", owner_body);
+    let sat: SecuredAccessTokenResponse = test::read_body_json(resp).await;
+
+    for name in ["publicboard", "privateboard"] {
+        let resp = request_auth!(
+            app,
+            addr,
+            delete,
+            &format!("/leaderboards/{name}"),
+            sat.token
+        );
+        assert!(resp.status().is_success(), "Failed to delete leaderboard");
+    }
+
+    let resp = request!(app, addr, delete, "/users/@me/delete", owner_body);
+    assert!(resp.status().is_success(), "Failed to delete user");
+
+    let resp = request!(app, addr2, delete, "/users/@me/delete", member_body);
+    assert!(resp.status().is_success(), "Failed to delete user");
+}
+
+#[actix_web::test]
+async fn leaderboard_seasons_work() {
+    let app = test::init_service(App::new().configure(init_test_services)).await;
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 80u16);
+
+    let body = json!({"username": "seasonowner", "password": "password"});
+    let resp = request!(app, addr, post, "/auth/register", body);
+    let user: NewUserIdentity = test::read_body_json(resp).await;
+
+    let now = chrono::Utc::now();
+    let batch = [600, 300, 0]
+        .map(|ago| json!({"timestamp": now.timestamp() - ago, "project_name": "seasons"}));
+    let resp = request_auth!(app, addr, post, "/activity/batch", user.auth_token, batch);
+    assert!(resp.status().is_success(), "Sending a batch should succeed");
+
+    let create = json!({"name": "seasonboard", "time_window": "calendar_year"});
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/create",
+        user.auth_token,
+        create
+    );
+    assert!(resp.status().is_success(), "Leaderboard creation failed");
+
+    // NOTE: Pretend that the current year has ended
+    let db = crate::database::DatabaseWrapper::from(Data::new(Database::new(
+        std::env::var("TEST_DATABASE").unwrap(),
+    )));
+    let next_year = now + chrono::Duration::days(366);
+    crate::api::leaderboards::snapshot_leaderboard_seasons(&db, next_year).await;
+    crate::api::leaderboards::snapshot_leaderboard_seasons(&db, next_year).await;
+
+    let resp = request_auth!(
+        app,
+        addr,
+        get,
+        "/leaderboards/seasonboard/seasons",
+        user.auth_token
+    );
+    let seasons: Vec<SeasonSummary> = test::read_body_json(resp).await;
+    assert_eq!(seasons.len(), 1, "Seasons should be recorded only once");
+    let winner = seasons[0]
+        .winner
+        .as_ref()
+        .expect("The season should have a winner");
+    assert_eq!(winner.username, "seasonowner");
+    assert_eq!(winner.time_coded, 600);
+
+    let resp = request!(app, addr, get, "/leaderboards/seasonboard/seasons");
+    assert!(
+        resp.status().is_client_error(),
+        "Seasons of private leaderboards should not be visible without logging in"
+    );
+
+    let resp = request_auth!(
+        app,
+        addr,
+        get,
+        &format!("/leaderboards/seasonboard/seasons/{}", seasons[0].period),
+        user.auth_token
+    );
+    let season: SeasonSnapshot = test::read_body_json(resp).await;
+    assert_eq!(season.members.len(), 1);
+    assert_eq!(season.members[0].position, 1);
+    assert_eq!(season.members[0].score, 600);
+
+    let resp = request_auth!(
+        app,
+        addr,
+        get,
+        "/leaderboards/seasonboard/seasons/1970",
+        user.auth_token
+    );
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    // NOTE: Pretend that the server was down for two more years
+    crate::api::leaderboards::snapshot_leaderboard_seasons(
+        &db,
+        now + chrono::Duration::days(3 * 366),
+    )
+    .await;
+
+    let resp = request_auth!(
+        app,
+        addr,
+        get,
+        "/leaderboards/seasonboard/seasons",
+        user.auth_token
+    );
+    let seasons: Vec<SeasonSummary> = test::read_body_json(resp).await;
+    assert_eq!(
+        seasons.len(),
+        3,
+        "Seasons that ended while the server was down should be recorded"
+    );
+
+    // NOTE: Only a limited amount of missed seasons is caught up on at a time
+    let much_later = now + chrono::Duration::days(15 * 366);
+    crate::api::leaderboards::snapshot_leaderboard_seasons(&db, much_later).await;
+
+    let resp = request_auth!(
+        app,
+        addr,
+        get,
+        "/leaderboards/seasonboard/seasons",
+        user.auth_token
+    );
+    let seasons: Vec<SeasonSummary> = test::read_body_json(resp).await;
+    assert_eq!(
+        seasons.len(),
+        13,
+        "At most 10 seasons should be caught up on"
+    );
+
+    crate::api::leaderboards::snapshot_leaderboard_seasons(&db, much_later).await;
+
+    let resp = request_auth!(
+        app,
+        addr,
+        get,
+        "/leaderboards/seasonboard/seasons",
+        user.auth_token
+    );
+    let seasons: Vec<SeasonSummary> = test::read_body_json(resp).await;
+    assert_eq!(
+        seasons.len(),
+        15,
+        "The rest of the seasons should be caught up on the next run"
+    );
+
+    let resp = request!(app, addr, post, "/auth/securedaccess", body);
+    let sat: SecuredAccessTokenResponse = test::read_body_json(resp).await;
+
+    let resp = request_auth!(app, addr, delete, "/leaderboards/seasonboard", sat.token);
+    assert!(resp.status().is_success(), "Failed to delete leaderboard");
+
+    let resp = request!(app, addr, delete, "/users/@me/delete", body);
+    assert!(resp.status().is_success(), "Failed to delete user");
+}
+
+#[actix_web::test]
+async fn ownership_and_renaming_work() {
+    let app = test::init_service(App::new().configure(init_test_services)).await;
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 80u16);
+    let addr2 = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)), 80u16);
+
+    let owner_body = json!({"username": "renameowner", "password": "password"});
+    let resp = request!(app, addr, post, "/auth/register", owner_body);
+    let owner: NewUserIdentity = test::read_body_json(resp).await;
+
+    let member_body = json!({"username": "renamemember", "password": "password"});
+    let resp = request!(app, addr2, post, "/auth/register", member_body);
+    let member: NewUserIdentity = test::read_body_json(resp).await;
+
+    let create = json!({"name": "renameboard", "avatar_url": "ftp://example.com/avatar.png"});
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/create",
+        owner.auth_token,
+        create
+    );
+    assert_eq!(
+        resp.status(),
+        StatusCode::BAD_REQUEST,
+        "Avatar urls should be http or https"
+    );
+
+    let create = json!({
+        "name": "renameboard",
+        "description": "Only the finest code",
+        "avatar_url": "https://example.com/avatar.png"
+    });
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/create",
+        owner.auth_token,
+        create
+    );
+    let created: serde_json::Value = test::read_body_json(resp).await;
+
+    let create = json!({"name": "takenboard"});
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/create",
+        owner.auth_token,
+        create
+    );
+    assert!(resp.status().is_success(), "Leaderboard creation failed");
+
+    let resp = request_auth!(
+        app,
+        addr2,
+        post,
+        "/leaderboards/join",
+        member.auth_token,
+        json!({"invite": created["invite_code"]})
+    );
+    assert!(resp.status().is_success(), "Joining leaderboard failed");
+
+    let resp = request!(app, addr, post, "/auth/securedaccess", owner_body);
+    let owner_sat: SecuredAccessTokenResponse = test::read_body_json(resp).await;
+    let resp = request!(app, addr2, post, "/auth/securedaccess", member_body);
+    let member_sat: SecuredAccessTokenResponse = test::read_body_json(resp).await;
+
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/renameboard/promote",
+        owner_sat.token,
+        json!({"user": "renamemember"})
+    );
+    assert!(resp.status().is_success(), "Promoting member failed");
+
+    let resp = request_auth!(
+        app,
+        addr2,
+        post,
+        "/leaderboards/renameboard/kick",
+        member_sat.token,
+        json!({"user": "renameowner"})
+    );
+    assert!(
+        resp.status().is_client_error(),
+        "Admins should not be able to kick the owner"
+    );
+
+    let resp = request_auth!(
+        app,
+        addr2,
+        post,
+        "/leaderboards/renameboard/rename",
+        member_sat.token,
+        json!({"name": "TakenBoard"})
+    );
+    assert_eq!(
+        resp.status(),
+        StatusCode::CONFLICT,
+        "Names should be unique case insensitively"
+    );
+
+    let resp = request_auth!(
+        app,
+        addr2,
+        post,
+        "/leaderboards/renameboard/rename",
+        member_sat.token,
+        json!({"name": "renamedboard"})
+    );
+    assert!(resp.status().is_success(), "Renaming failed");
+
+    let resp = request_auth!(
+        app,
+        addr,
+        get,
+        "/leaderboards/renamedboard",
+        owner.auth_token
+    );
+    let board: PrivateLeaderboard = test::read_body_json(resp).await;
+    assert_eq!(board.owner.as_deref(), Some("renameowner"));
+    assert_eq!(
+        board.config.description.as_deref(),
+        Some("Only the finest code")
+    );
+
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/renamedboard/leave",
+        owner_sat.token
+    );
+    assert!(
+        resp.status().is_client_error(),
+        "The owner should not be able to leave"
+    );
+
+    let resp = request_auth!(
+        app,
+        addr2,
+        post,
+        "/leaderboards/renamedboard/transfer",
+        member_sat.token,
+        json!({"user": "renamemember"})
+    );
+    assert!(
+        resp.status().is_client_error(),
+        "Only the owner should be able to transfer the ownership"
+    );
+
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/renamedboard/transfer",
+        owner_sat.token,
+        json!({"user": "renamemember"})
+    );
+    assert!(
+        resp.status().is_success(),
+        "Transferring the ownership failed"
+    );
+
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/renamedboard/leave",
+        owner_sat.token
+    );
+    assert!(
+        resp.status().is_success(),
+        "The previous owner should be able to leave"
+    );
+
+    let resp = request_auth!(
+        app,
+        addr2,
+        delete,
+        "/leaderboards/renamedboard",
+        member_sat.token
+    );
+    assert!(resp.status().is_success(), "Failed to delete leaderboard");
+
+    let resp = request_auth!(
+        app,
+        addr,
+        delete,
+        "/leaderboards/takenboard",
+        owner_sat.token
+    );
+    assert!(resp.status().is_success(), "Failed to delete leaderboard");
+
+    let resp = request!(app, addr, delete, "/users/@me/delete", owner_body);
+    assert!(resp.status().is_success(), "Failed to delete user");
+
+    let resp = request!(app, addr2, delete, "/users/@me/delete", member_body);
+    assert!(resp.status().is_success(), "Failed to delete user");
+}
+
+#[actix_web::test]
+async fn invite_links_work() {
+    let app = test::init_service(App::new().configure(init_test_services)).await;
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 80u16);
+
+    let mut users = Vec::new();
+    for (i, username) in ["inviteowner", "invitemember", "invitelate"]
+        .into_iter()
+        .enumerate()
+    {
+        let body = json!({"username": username, "password": "password"});
+        let user_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 1, i as u8)), 80u16);
+        let resp = request!(app, user_addr, post, "/auth/register", body);
+        let user: NewUserIdentity = test::read_body_json(resp).await;
+        users.push((body, user));
+    }
+    let (owner_body, owner) = &users[0];
+
+    let create = json!({"name": "inviteboard"});
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/create",
+        owner.auth_token,
+        create
+    );
+    assert!(resp.status().is_success(), "Leaderboard creation failed");
+
+    let resp = request!(app, addr, post, "/auth/securedaccess", owner_body);
+    let sat: SecuredAccessTokenResponse = test::read_body_json(resp).await;
+
+    let expired = json!({"name": "expired", "expires_at": 1});
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/inviteboard/invites",
+        sat.token,
+        expired
+    );
+    assert!(
+        resp.status().is_client_error(),
+        "Invites should not be created already expired"
+    );
+
+    let link = json!({
+        "name": "once",
+        "expires_at": chrono::Utc::now().timestamp() + 3600,
+        "max_uses": 1
+    });
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/inviteboard/invites",
+        sat.token,
+        link
+    );
+    let created: InviteLink = test::read_body_json(resp).await;
+
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/inviteboard/invites",
+        sat.token,
+        link
+    );
+    assert_eq!(
+        resp.status(),
+        StatusCode::CONFLICT,
+        "Invite names should be unique"
+    );
+
+    let invite = json!({"invite": format!("ttlic_{}", created.invite_code)});
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/join",
+        users[1].1.auth_token,
+        invite
+    );
+    assert!(
+        resp.status().is_success(),
+        "Joining with an invite link failed"
+    );
+
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/join",
+        users[2].1.auth_token,
+        invite
+    );
+    assert_eq!(
+        resp.status(),
+        StatusCode::FORBIDDEN,
+        "Used up invites should not work"
+    );
+
+    let resp = request_auth!(
+        app,
+        addr,
+        get,
+        "/leaderboards/inviteboard/invites",
+        owner.auth_token
+    );
+    let links: Vec<InviteLink> = test::read_body_json(resp).await;
+    assert_eq!(links.len(), 1);
+    assert_eq!(links[0].uses, 1, "Uses should be counted");
+
+    let resp = request_auth!(
+        app,
+        addr,
+        get,
+        "/leaderboards/inviteboard/invites",
+        users[1].1.auth_token
+    );
+    assert!(
+        resp.status().is_client_error(),
+        "Only admins should see the invites"
+    );
+
+    let resp = request_auth!(
+        app,
+        addr,
+        delete,
+        "/leaderboards/inviteboard/invites/once",
+        sat.token
+    );
+    assert!(resp.status().is_success(), "Revoking the invite failed");
+
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/join",
+        users[2].1.auth_token,
+        invite
+    );
+    assert_eq!(
+        resp.status(),
+        StatusCode::NOT_FOUND,
+        "Revoked invites should not work"
+    );
+
+    let resp = request_auth!(app, addr, delete, "/leaderboards/inviteboard", sat.token);
+    assert!(resp.status().is_success(), "Failed to delete leaderboard");
+
+    for (body, _) in &users {
+        let resp = request!(app, addr, delete, "/users/@me/delete", body);
+        assert!(resp.status().is_success(), "Failed to delete user");
+    }
+}
+
+#[actix_web::test]
+async fn join_requests_work() {
+    let app = test::init_service(App::new().configure(init_test_services)).await;
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 80u16);
+    let addr2 = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)), 80u16);
+
+    let owner_body = json!({"username": "approvalowner", "password": "password"});
+    let resp = request!(app, addr, post, "/auth/register", owner_body);
+    let owner: NewUserIdentity = test::read_body_json(resp).await;
+
+    let member_body = json!({"username": "approvalmember", "password": "password"});
+    let resp = request!(app, addr2, post, "/auth/register", member_body);
+    let member: NewUserIdentity = test::read_body_json(resp).await;
+
+    let create = json!({"name": "approvalboard", "approval_required": true});
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/create",
+        owner.auth_token,
+        create
+    );
+    assert!(resp.status().is_success(), "Leaderboard creation failed");
+
+    let resp = request!(app, addr, post, "/auth/securedaccess", owner_body);
+    let sat: SecuredAccessTokenResponse = test::read_body_json(resp).await;
+
+    let link = json!({"name": "approvallink", "max_uses": 1});
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/approvalboard/invites",
+        sat.token,
+        link
+    );
+    let link: InviteLink = test::read_body_json(resp).await;
+    let invite = json!({"invite": format!("ttlic_{}", link.invite_code)});
+
+    let resp = request_auth!(
+        app,
+        addr2,
+        post,
+        "/leaderboards/join",
+        member.auth_token,
+        invite
+    );
+    assert_eq!(
+        resp.status(),
+        StatusCode::ACCEPTED,
+        "Joining should create a request"
+    );
+
+    let resp = request_auth!(
+        app,
+        addr2,
+        post,
+        "/leaderboards/join",
+        member.auth_token,
+        invite
+    );
+    assert_eq!(
+        resp.status(),
+        StatusCode::CONFLICT,
+        "Requesting twice should not be possible"
+    );
+
+    let resp = request_auth!(
+        app,
+        addr2,
+        get,
+        "/leaderboards/approvalboard",
+        member.auth_token
+    );
+    assert!(
+        resp.status().is_client_error(),
+        "Pending members should not see the leaderboard"
+    );
+
+    let resp = request_auth!(
+        app,
+        addr,
+        get,
+        "/leaderboards/approvalboard/requests",
+        owner.auth_token
+    );
+    let requests: Vec<JoinRequest> = test::read_body_json(resp).await;
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].username, "approvalmember");
+
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/approvalboard/requests/reject",
+        sat.token,
+        json!({"user": "approvalmember"})
+    );
+    assert!(resp.status().is_success(), "Rejecting the request failed");
+
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/approvalboard/requests/approve",
+        sat.token,
+        json!({"user": "approvalmember"})
+    );
+    assert_eq!(
+        resp.status(),
+        StatusCode::NOT_FOUND,
+        "Rejected requests should be removed"
+    );
+
+    let resp = request_auth!(
+        app,
+        addr,
+        get,
+        "/leaderboards/approvalboard/invites",
+        owner.auth_token
+    );
+    let links: Vec<InviteLink> = test::read_body_json(resp).await;
+    assert_eq!(
+        links[0].uses, 0,
+        "Rejected requests should not use the invite"
+    );
+
+    let resp = request_auth!(
+        app,
+        addr2,
+        post,
+        "/leaderboards/join",
+        member.auth_token,
+        invite
+    );
+    assert_eq!(resp.status(), StatusCode::ACCEPTED);
+
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/approvalboard/requests/approve",
+        sat.token,
+        json!({"user": "approvalmember"})
+    );
+    assert!(resp.status().is_success(), "Approving the request failed");
+
+    let resp = request_auth!(
+        app,
+        addr2,
+        get,
+        "/leaderboards/approvalboard",
+        member.auth_token
+    );
+    let board: PrivateLeaderboard = test::read_body_json(resp).await;
+    assert_eq!(board.members.len(), 2, "Approved members should be added");
+
+    let resp = request_auth!(
+        app,
+        addr,
+        get,
+        "/leaderboards/approvalboard/invites",
+        owner.auth_token
+    );
+    let links: Vec<InviteLink> = test::read_body_json(resp).await;
+    assert_eq!(links[0].uses, 1, "Approved requests should use the invite");
+
+    let resp = request_auth!(app, addr, delete, "/leaderboards/approvalboard", sat.token);
+    assert!(resp.status().is_success(), "Failed to delete leaderboard");
+
+    let resp = request!(app, addr, delete, "/users/@me/delete", owner_body);
+    assert!(resp.status().is_success(), "Failed to delete user");
+
+    let resp = request!(app, addr2, delete, "/users/@me/delete", member_body);
+    assert!(resp.status().is_success(), "Failed to delete user");
+}
+
+#[actix_web::test]
+async fn bans_work() {
+    let app = test::init_service(App::new().configure(init_test_services)).await;
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 80u16);
+    let addr2 = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)), 80u16);
+
+    let owner_body = json!({"username": "banowner", "password": "password"});
+    let resp = request!(app, addr, post, "/auth/register", owner_body);
+    let owner: NewUserIdentity = test::read_body_json(resp).await;
+
+    let member_body = json!({"username": "banmember", "password": "password"});
+    let resp = request!(app, addr2, post, "/auth/register", member_body);
+    let member: NewUserIdentity = test::read_body_json(resp).await;
+
+    let create = json!({"name": "banboard"});
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/create",
+        owner.auth_token,
+        create
+    );
+    let created: serde_json::Value = test::read_body_json(resp).await;
+    let invite = json!({"invite": created["invite_code"]});
+
+    let resp = request_auth!(
+        app,
+        addr2,
+        post,
+        "/leaderboards/join",
+        member.auth_token,
+        invite
+    );
+    assert!(resp.status().is_success(), "Joining leaderboard failed");
+
+    let resp = request!(app, addr, post, "/auth/securedaccess", owner_body);
+    let sat: SecuredAccessTokenResponse = test::read_body_json(resp).await;
+
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/banboard/bans",
+        sat.token,
+        json!({"user": "banowner"})
+    );
+    assert!(
+        resp.status().is_client_error(),
+        "Admins should not be able to ban themselves"
+    );
+
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/leaderboards/banboard/bans",
+        sat.token,
+        json!({"user": "banmember"})
+    );
+    assert!(resp.status().is_success(), "Banning member failed");
+
+    let resp = request_auth!(app, addr, get, "/leaderboards/banboard", owner.auth_token);
+    let board: PrivateLeaderboard = test::read_body_json(resp).await;
+    assert_eq!(board.members.len(), 1, "Banned members should be removed");
+
+    let resp = request_auth!(
+        app,
+        addr,
+        get,
+        "/leaderboards/banboard/bans",
+        owner.auth_token
+    );
+    let bans: Vec<LeaderboardBan> = test::read_body_json(resp).await;
+    assert_eq!(bans.len(), 1);
+    assert_eq!(bans[0].username, "banmember");
+
+    let resp = request_auth!(
+        app,
+        addr2,
+        post,
+        "/leaderboards/join",
+        member.auth_token,
+        invite
+    );
+    assert_eq!(
+        resp.status(),
+        StatusCode::FORBIDDEN,
+        "Banned users should not be able to rejoin"
+    );
+
+    let resp = request_auth!(
+        app,
+        addr,
+        delete,
+        "/leaderboards/banboard/bans/banmember",
+        sat.token
+    );
+    assert!(resp.status().is_success(), "Unbanning member failed");
+
+    let resp = request_auth!(
+        app,
+        addr,
+        delete,
+        "/leaderboards/banboard/bans/banmember",
+        sat.token
+    );
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    let resp = request_auth!(
+        app,
+        addr2,
+        post,
+        "/leaderboards/join",
+        member.auth_token,
+        invite
+    );
+    assert!(
+        resp.status().is_success(),
+        "Unbanned users should be able to rejoin"
+    );
+
+    let resp = request_auth!(app, addr, delete, "/leaderboards/banboard", sat.token);
+    assert!(resp.status().is_success(), "Failed to delete leaderboard");
+
+    let resp = request!(app, addr, delete, "/users/@me/delete", owner_body);
+    assert!(resp.status().is_success(), "Failed to delete user");
+
+    let resp = request!(app, addr2, delete, "/users/@me/delete", member_body);
+    assert!(resp.status().is_success(), "Failed to delete user");
+}
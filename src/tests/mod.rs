@@ -5,6 +5,8 @@ mod auth;
 mod friends;
 mod leaderboards;
 mod macros;
+mod utils;
+mod wakatime;
 
 use std::{num::NonZeroU32, sync::Arc};
 
@@ -33,6 +35,8 @@ fn init_test_services(cfg: &mut ServiceConfig) {
 
     let heartbeat_store = Data::new(crate::api::activity::HeartBeatMemoryStore::new());
 
+    let alias_cache = Data::new(crate::api::activity::ProjectAliasCache::new());
+
     let secured_access_token_storage = Data::new(crate::SecuredAccessTokenStorage::new());
 
     #[cfg(feature = "testausid")]
@@ -63,6 +67,9 @@ fn init_test_services(cfg: &mut ServiceConfig) {
             .app_data(Data::new(Database::new(db_url)))
             .app_data(query_config)
             .app_data(Data::clone(&secured_access_token_storage))
+            .app_data(Data::new(crate::api::activity::SessionIdleTimeout(
+                chrono::Duration::seconds(900),
+            )))
             .wrap(cors)
             .service(crate::api::health)
             .service(crate::api::auth::register)
@@ -77,9 +84,18 @@ fn init_test_services(cfg: &mut ServiceConfig) {
                     .service({
                         web::scope("/activity")
                             .service(crate::api::activity::update)
+                            .service(crate::api::activity::batch)
                             .service(crate::api::activity::delete)
+                            .service(crate::api::activity::bulk_delete)
+                            .service(crate::api::activity::bulk_update)
+                            .service(crate::api::activity::add_manual_activity)
+                            .service(crate::api::activity::edit_manual_activity)
+                            .service(crate::api::activity::delete_manual_activity)
                             .service(crate::api::activity::flush)
                             .service(crate::api::activity::rename_project)
+                            .service(crate::api::activity::get_aliases)
+                            .service(crate::api::activity::add_alias)
+                            .service(crate::api::activity::delete_alias)
                     })
                     .service(crate::api::auth::login)
                     .service(crate::api::auth::regenerate)
@@ -87,8 +103,16 @@ fn init_test_services(cfg: &mut ServiceConfig) {
                     .service(crate::api::auth::changepassword)
                     .service(crate::api::auth::get_secured_access_token)
                     .service(crate::api::account::change_settings)
+                    .service(crate::api::account::get_privacy_settings)
+                    .service(crate::api::account::change_privacy_settings)
                     .service(crate::api::friends::add_friend)
                     .service(crate::api::friends::get_friends)
+                    .service(crate::api::friends::get_friend_requests)
+                    .service(crate::api::friends::accept_friend_request)
+                    .service(crate::api::friends::decline_friend_request)
+                    .service(crate::api::friends::block_friend_request)
+                    .service(crate::api::friends::get_friend_visibility)
+                    .service(crate::api::friends::set_friend_visibility)
                     .service(crate::api::friends::regenerate_friend_code)
                     .service(crate::api::friends::remove)
                     .service(crate::api::users::my_profile)
@@ -96,25 +120,53 @@ fn init_test_services(cfg: &mut ServiceConfig) {
                     .service(crate::api::users::get_current_activity)
                     .service(crate::api::users::delete_user)
                     .service(crate::api::users::my_leaderboards)
+                    .service(crate::api::users::get_blocked_users)
+                    .service(crate::api::users::block_user)
+                    .service(crate::api::users::unblock_user)
                     .service(crate::api::users::get_activity_summary)
+                    .service(crate::api::users::get_activity_aggregate)
+                    .service(crate::api::users::get_goals)
+                    .service(crate::api::users::set_goals)
+                    .service(crate::api::users::get_streak)
+                    .service(crate::api::users::export_data)
                     .service(crate::api::leaderboards::create_leaderboard)
+                    .service(crate::api::leaderboards::search_leaderboards)
                     .service(crate::api::leaderboards::get_leaderboard)
                     .service(crate::api::leaderboards::join_leaderboard)
+                    .service(crate::api::leaderboards::join_public_leaderboard)
                     .service(crate::api::leaderboards::leave_leaderboard)
                     .service(crate::api::leaderboards::delete_leaderboard)
                     .service(crate::api::leaderboards::promote_member)
                     .service(crate::api::leaderboards::demote_member)
                     .service(crate::api::leaderboards::kick_member)
                     .service(crate::api::leaderboards::regenerate_invite)
+                    .service(crate::api::leaderboards::rename_leaderboard)
+                    .service(crate::api::leaderboards::transfer_ownership)
+                    .service(crate::api::leaderboards::create_invite_link)
+                    .service(crate::api::leaderboards::get_invite_links)
+                    .service(crate::api::leaderboards::delete_invite_link)
+                    .service(crate::api::leaderboards::get_join_requests)
+                    .service(crate::api::leaderboards::approve_join_request)
+                    .service(crate::api::leaderboards::reject_join_request)
+                    .service(crate::api::leaderboards::ban_member)
+                    .service(crate::api::leaderboards::get_bans)
+                    .service(crate::api::leaderboards::unban_member)
+                    .service(crate::api::leaderboards::change_settings)
+                    .service(crate::api::leaderboards::get_seasons)
+                    .service(crate::api::leaderboards::get_season)
                     .service(crate::api::search::search_public_users)
-                    .service(crate::api::stats::stats);
+                    .service(crate::api::stats::stats)
+                    .service(crate::api::wakatime::send_heartbeat)
+                    .service(crate::api::wakatime::send_heartbeats_bulk)
+                    .service(crate::api::wakatime::import);
                 #[cfg(feature = "testausid")]
                 {
                     scope.service(crate::api::oauth::callback)
                 }
             }),
     )
-    .app_data(Data::clone(&heartbeat_store));
+    .app_data(Data::clone(&heartbeat_store))
+    .app_data(Data::clone(&alias_cache));
     #[cfg(feature = "testausid")]
     {
         cfg.app_data(Data::new(client));
@@ -0,0 +1,20 @@
+use chrono::{NaiveDate, TimeZone, Utc};
+
+use crate::utils::midnight;
+
+#[actix_web::test]
+async fn midnight_skipped_by_daylight_saving_time() {
+    // NOTE: Clocks in Sao Paulo went from 00:00 to 01:00 when daylight saving time started
+    let date = NaiveDate::from_ymd_opt(2018, 11, 4).unwrap();
+    assert_eq!(
+        midnight(chrono_tz::America::Sao_Paulo, date),
+        Utc.with_ymd_and_hms(2018, 11, 4, 3, 0, 0).unwrap(),
+        "The day should start at the first valid local time"
+    );
+
+    let date = NaiveDate::from_ymd_opt(2018, 11, 5).unwrap();
+    assert_eq!(
+        midnight(chrono_tz::America::Sao_Paulo, date),
+        Utc.with_ymd_and_hms(2018, 11, 5, 2, 0, 0).unwrap()
+    );
+}
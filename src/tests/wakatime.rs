@@ -0,0 +1,361 @@
+use std::{
+    collections::HashSet,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+};
+
+use actix_web::{
+    http::header::HeaderValue,
+    test::{self, TestRequest},
+};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use serde_json::json;
+
+use super::{macros::*, *};
+use crate::models::{CurrentActivity, NewUserIdentity, SecuredAccessTokenResponse};
+
+#[actix_web::test]
+async fn wakatime_heartbeats_work() {
+    let app = test::init_service(App::new().configure(init_test_services)).await;
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 80u16);
+
+    let body = json!({"username": "wakatimeuser", "password": "password"});
+    let resp = request!(app, addr, post, "/auth/register", body);
+    let user: NewUserIdentity = test::read_body_json(resp).await;
+
+    let basic_auth = "Basic ".to_owned() + &BASE64_STANDARD.encode(&user.auth_token);
+    let now = chrono::Utc::now().timestamp() as f64;
+
+    let heartbeat = json!({
+        "entity": "/home/user/project/src/main.rs",
+        "type": "file",
+        "time": now,
+        "project": "project",
+        "language": "Rust",
+        "is_write": true,
+        "user_agent": "wakatime/v1.73.0 (linux-5.15.0) go1.20.2 vscode/1.77.0 vscode-wakatime/24.0.10",
+    });
+
+    let resp = TestRequest::post()
+        .peer_addr(addr)
+        .uri("/api/v1/users/current/heartbeats")
+        .insert_header(("authorization", basic_auth.clone()))
+        .insert_header(("x-machine-name", "hostname"))
+        .set_json(&heartbeat)
+        .send_request(&app)
+        .await;
+    assert!(
+        resp.status().is_success(),
+        "Sending a WakaTime heartbeat should succeed"
+    );
+
+    let resp = request_auth!(
+        app,
+        addr,
+        get,
+        "/users/@me/activity/current",
+        user.auth_token
+    );
+    let current: CurrentActivity = test::read_body_json(resp).await;
+    assert_eq!(
+        current.heartbeat.editor_name.as_deref(),
+        Some("vscode"),
+        "Editor should be parsed from the user agent"
+    );
+    assert_eq!(
+        current.heartbeat.hostname.as_deref(),
+        Some("hostname"),
+        "Hostname should be taken from the machine name"
+    );
+
+    let bulk = json!([
+        {
+            "entity": "/home/user/other/main.py",
+            "time": now - 3600.0,
+            "project": "other",
+            "language": "Python",
+        },
+        {
+            "entity": "/home/user/other/main.py",
+            "time": now - 3540.0,
+            "project": "other",
+            "language": "Python",
+        },
+        {
+            "entity": "/home/user/other/main.py",
+            "time": now - 3500.0,
+            "project": "a".repeat(65),
+        },
+    ]);
+
+    let resp = TestRequest::post()
+        .peer_addr(addr)
+        .uri("/api/v1/users/current/heartbeats.bulk")
+        .insert_header(("authorization", basic_auth.as_str()))
+        .set_json(&bulk)
+        .send_request(&app)
+        .await;
+    assert!(
+        resp.status().is_success(),
+        "Sending WakaTime heartbeats in bulk should succeed"
+    );
+
+    let result: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(
+        result["responses"][2][1], 400,
+        "Invalid heartbeats should be reported"
+    );
+
+    let resp = request_auth!(app, addr, get, "/users/@me/activity/data", user.auth_token);
+    let data: Vec<serde_json::Value> = test::read_body_json(resp).await;
+    let session = data
+        .iter()
+        .find(|a| a["project_name"] == "other")
+        .expect("Old heartbeats should be stored");
+    assert_eq!(session["duration"], 60, "Timestamps should be used");
+
+    let resp = TestRequest::post()
+        .peer_addr(addr)
+        .uri("/api/v1/users/current/heartbeats")
+        .insert_header(("authorization", "Basic invalid"))
+        .set_json(&heartbeat)
+        .send_request(&app)
+        .await;
+    assert!(
+        resp.status().is_client_error(),
+        "Invalid credentials should be rejected"
+    );
+
+    let resp = TestRequest::get()
+        .peer_addr(addr)
+        .uri("/users/@me")
+        .insert_header(("authorization", basic_auth.as_str()))
+        .send_request(&app)
+        .await;
+    assert!(
+        resp.status().is_client_error(),
+        "Basic authentication should only work for WakaTime heartbeats"
+    );
+
+    let resp = TestRequest::get()
+        .peer_addr(addr)
+        .uri("/users/@me")
+        .insert_header((
+            "authorization",
+            HeaderValue::from_bytes(b"Bearer \xff").unwrap(),
+        ))
+        .send_request(&app)
+        .await;
+    assert_eq!(
+        resp.status(),
+        StatusCode::UNAUTHORIZED,
+        "Non-ASCII authorization should be treated as missing"
+    );
+
+    let heartbeat = json!({
+        "time": now,
+        "project": "project",
+        "user_agent": format!("wakatime/v1.73.0 {}/1.0.0", "e".repeat(40)),
+    });
+
+    let resp = TestRequest::post()
+        .peer_addr(addr)
+        .uri("/api/v1/users/current/heartbeats")
+        .insert_header(("authorization", basic_auth.as_str()))
+        .insert_header(("x-machine-name", "h".repeat(40)))
+        .set_json(&heartbeat)
+        .send_request(&app)
+        .await;
+    assert!(
+        resp.status().is_success(),
+        "Heartbeats with long machine names should be accepted"
+    );
+
+    let resp = request_auth!(
+        app,
+        addr,
+        get,
+        "/users/@me/activity/current",
+        user.auth_token
+    );
+    let current: CurrentActivity = test::read_body_json(resp).await;
+    assert_eq!(
+        current.heartbeat.hostname,
+        Some("h".repeat(32)),
+        "Long machine names should be shortened"
+    );
+    assert_eq!(
+        current.heartbeat.editor_name,
+        Some("e".repeat(32)),
+        "Long editor names should be shortened"
+    );
+
+    let resp = request!(app, addr, delete, "/users/@me/delete", body);
+    assert!(resp.status().is_success(), "Failed to delete user");
+}
+
+#[actix_web::test]
+async fn wakatime_import_works() {
+    let app = test::init_service(App::new().configure(init_test_services)).await;
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 80u16);
+
+    let body = json!({"username": "wakatimeimporter", "password": "password"});
+    let resp = request!(app, addr, post, "/auth/register", body);
+    let user: NewUserIdentity = test::read_body_json(resp).await;
+
+    let resp = request!(app, addr, post, "/auth/securedaccess", body);
+    let sat: SecuredAccessTokenResponse = test::read_body_json(resp).await;
+
+    let export = json!({
+        "user": { "username": "wakatimeimporter" },
+        "days": [
+            {
+                "date": "2022-10-01",
+                "grand_total": { "total_seconds": 5400.0 },
+                "projects": [
+                    {
+                        "name": "project",
+                        "grand_total": { "total_seconds": 3600.0 },
+                        "languages": [
+                            { "name": "Rust", "total_seconds": 3000.4 },
+                            { "name": "TOML", "total_seconds": 599.6 },
+                        ],
+                        "editors": [{ "name": "Neovim", "total_seconds": 3600.0 }],
+                        "machines": [{ "name": "hostname", "total_seconds": 3600.0 }],
+                    },
+                    {
+                        "name": "other",
+                        "grand_total": { "total_seconds": 1800.0 },
+                    },
+                    {
+                        "name": "a".repeat(65),
+                        "grand_total": { "total_seconds": 600.0 },
+                    },
+                ],
+            },
+            {
+                "date": "2022-10-02",
+                "grand_total": { "total_seconds": 0.0 },
+                "projects": [],
+            },
+            {
+                "date": "2022-10-03",
+                "grand_total": { "total_seconds": 100000.0 },
+                "projects": [
+                    {
+                        "name": "forever",
+                        "grand_total": { "total_seconds": 100000.0 },
+                    },
+                ],
+            },
+            {
+                "date": "2022-10-04",
+                "grand_total": { "total_seconds": 100000.0 },
+                "projects": [
+                    {
+                        "name": "first",
+                        "grand_total": { "total_seconds": 50000.0 },
+                    },
+                    {
+                        "name": "second",
+                        "grand_total": { "total_seconds": 50000.0 },
+                    },
+                ],
+            },
+        ],
+    });
+
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/users/@me/import/wakatime",
+        user.auth_token,
+        export
+    );
+    assert!(
+        resp.status().is_client_error(),
+        "Importing should require secured access"
+    );
+
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/users/@me/import/wakatime?dry_run=true",
+        sat.token,
+        export
+    );
+    assert!(resp.status().is_success(), "Dry run should succeed");
+
+    let result: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(result["activities"], 3, "Dry run should count activities");
+    assert_eq!(result["total_seconds"], 5400, "Dry run should count time");
+    assert_eq!(
+        result["skipped"].as_array().map(Vec::len),
+        Some(3),
+        "Over-long names and days with over 24 hours should be skipped"
+    );
+
+    let resp = request_auth!(app, addr, get, "/users/@me/activity/data", user.auth_token);
+    let data: Vec<serde_json::Value> = test::read_body_json(resp).await;
+    assert!(data.is_empty(), "Dry run should not import anything");
+
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/users/@me/import/wakatime",
+        sat.token,
+        export
+    );
+    assert!(resp.status().is_success(), "Importing should succeed");
+
+    let resp = request_auth!(app, addr, get, "/users/@me/activity/data", user.auth_token);
+    let data: Vec<serde_json::Value> = test::read_body_json(resp).await;
+    assert_eq!(data.len(), 3, "Activities should be imported");
+
+    let rust = data
+        .iter()
+        .find(|a| a["language"] == "Rust")
+        .expect("Languages should be imported");
+    assert_eq!(rust["editor_name"], "Neovim", "Editor should be imported");
+
+    let start_times = data
+        .iter()
+        .map(|a| a["start_time"].as_str().unwrap_or_default())
+        .collect::<HashSet<_>>();
+    assert_eq!(
+        start_times.len(),
+        3,
+        "Imported activities should not overlap each other"
+    );
+
+    let resp = request_auth!(
+        app,
+        addr,
+        post,
+        "/users/@me/import/wakatime",
+        sat.token,
+        export
+    );
+    assert!(resp.status().is_success(), "Importing again should succeed");
+
+    let result: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(result["activities"], 0, "Imported days should be skipped");
+    assert_eq!(result["skipped_days"], 1);
+    assert_eq!(
+        result["skipped"][0]["date"], "2022-10-01",
+        "Skipped days should be reported"
+    );
+
+    let resp = request_auth!(app, addr, get, "/users/@me/activity/data", user.auth_token);
+    let data: Vec<serde_json::Value> = test::read_body_json(resp).await;
+    assert_eq!(
+        data.len(),
+        3,
+        "Importing again should not duplicate activities"
+    );
+
+    let resp = request!(app, addr, delete, "/users/@me/delete", body);
+    assert!(resp.status().is_success(), "Failed to delete user");
+}
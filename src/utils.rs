@@ -1,10 +1,7 @@
-use std::collections::HashMap;
-
-use itertools::Itertools;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
 use rand::{distributions::Alphanumeric, thread_rng, Rng};
 
-use crate::models::CodingActivity;
-
 pub fn generate_token() -> String {
     thread_rng()
         .sample_iter(&Alphanumeric)
@@ -21,13 +18,33 @@ pub fn generate_friend_code() -> String {
         .collect()
 }
 
-pub fn group_by_language(iter: impl Iterator<Item = CodingActivity>) -> HashMap<String, i32> {
-    iter.map(|d| {
-        (
-            d.language.unwrap_or_else(|| String::from("none")),
-            d.duration,
+/// Returns the start of the given day in the given timezone
+pub fn midnight(timezone: Tz, date: NaiveDate) -> DateTime<Utc> {
+    // NOTE: Midnight doesn't exist on some days when daylight saving time starts,
+    // then the day starts at the first valid time after the gap
+    (0..24 * 4)
+        .filter_map(|quarter| date.and_hms_opt(quarter / 4, quarter % 4 * 15, 0))
+        .find_map(|time| timezone.from_local_datetime(&time).earliest())
+        .map_or_else(
+            || Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).expect("bug: impossible")),
+            |t| t.with_timezone(&Utc),
         )
-    })
-    .into_grouping_map()
-    .sum()
+}
+
+/// Returns the start of the day `days` days ago in the given timezone
+pub fn days_ago_at_midnight(timezone: Tz, days: i64) -> DateTime<Utc> {
+    midnight(
+        timezone,
+        Utc::now().with_timezone(&timezone).date_naive() - Duration::days(days),
+    )
+}
+
+/// Returns the start of the current week (Monday) in the given timezone
+pub fn start_of_week(timezone: Tz) -> DateTime<Utc> {
+    let today = Utc::now().with_timezone(&timezone).date_naive();
+
+    midnight(
+        timezone,
+        today - Duration::days(today.weekday().num_days_from_monday().into()),
+    )
 }